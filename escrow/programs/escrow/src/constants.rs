@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+// Maximum number of mints on each side of a bundle offer
+#[constant]
+pub const MAX_BUNDLE_LEGS: u8 = 5;
+
+// Hard cap on the protocol fee the admin can set, in basis points (5%)
+#[constant]
+pub const MAX_FEE_BPS: u16 = 500;
+
+// Number of order nonces tracked by one OrderNonces bitmap page
+#[constant]
+pub const NONCES_PER_PAGE: u64 = 1024;
+
+// Maximum number of milestones in one milestone escrow
+#[constant]
+pub const MAX_MILESTONES: u8 = 10;
+
+// Hard cap on the fee an arbiter can take when resolving a dispute, in basis points (10%)
+#[constant]
+pub const MAX_ARBITER_FEE_BPS: u16 = 1_000;
+
+// Time an arbiter has to rule on a dispute, in seconds (30 days), after which the funds
+// can be refunded as if there were no dispute
+#[constant]
+pub const DISPUTE_TIMEOUT: i64 = 30 * 24 * 60 * 60;
+
+// Number of open escrows listed on one OfferBook page
+#[constant]
+pub const OFFERS_PER_PAGE: u8 = 64;
+
+// Fixed-point scale of take_many's max_price, which is token B per token A (in base units)
+// multiplied by PRICE_SCALE
+#[constant]
+pub const PRICE_SCALE: u64 = 1_000_000_000;
@@ -0,0 +1,158 @@
+use anchor_lang::prelude::*;
+
+// ===== ESCROW ERRORS =====
+// Custom errors returned by the escrow program
+#[error_code]
+pub enum EscrowError {
+    // Deposit, receive and fill amounts must all be greater than zero
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+
+    // A taker tried to pay more token B than the offer still asks for
+    #[msg("Fill amount exceeds the remaining amount of the offer")]
+    FillExceedsRemaining,
+
+    // The pro-rata amount of token A for this fill rounds down to zero
+    #[msg("Fill amount is too small to receive any token A")]
+    FillTooSmall,
+
+    // The expiry passed to make is not in the future
+    #[msg("Expiry must be in the future")]
+    InvalidExpiry,
+
+    // The offer can no longer be taken
+    #[msg("Offer has expired")]
+    OfferExpired,
+
+    // The permissionless refund crank was called on a live offer
+    #[msg("Offer has not expired yet")]
+    OfferNotExpired,
+
+    // The taker is not the named counterparty or is not in the offer's allowlist
+    #[msg("Taker is not allowed to take this offer")]
+    UnauthorizedTaker,
+
+    // A token account that is optional for native SOL was left out for an SPL mint
+    #[msg("Token account is required for SPL mints")]
+    MissingTokenAccount,
+
+    // Token-2022 mint with an extension such as non-transferable or permanent delegate
+    #[msg("Mint has a Token-2022 extension the escrow does not support")]
+    UnsupportedMintExtension,
+
+    // A bundle side is empty, too long or names the same mint twice
+    #[msg("Bundle legs must be non-empty, within the maximum and use distinct mints")]
+    InvalidBundleLegs,
+
+    // remaining_accounts do not match the legs of the bundle
+    #[msg("Bundle accounts do not match the bundle legs")]
+    InvalidBundleAccounts,
+
+    // The signer is not allowed to perform this action
+    #[msg("Unauthorized action")]
+    Unauthorized,
+
+    // The protocol fee is above the hard-coded maximum
+    #[msg("Fee is above the maximum of 500 basis points")]
+    InvalidFee,
+
+    // The Ed25519 instruction before fill_order is missing or does not cover this order
+    #[msg("Order signature is missing or invalid")]
+    InvalidOrderSignature,
+
+    // The order nonce was already filled or cancelled
+    #[msg("Order nonce has already been used")]
+    OrderNonceUsed,
+
+    // Milestone list is empty, too long or has a zero amount
+    #[msg("Milestones must be non-empty, within the maximum and have non-zero amounts")]
+    InvalidMilestones,
+
+    // The milestone index does not exist or was already released
+    #[msg("Milestone does not exist or has already been released")]
+    InvalidMilestone,
+
+    // Refund of a milestone escrow before both parties approved it or the deadline passed
+    #[msg("Refund needs approval from both parties or a passed deadline")]
+    RefundNotAllowed,
+
+    // Arbiter terms at make time are not usable
+    #[msg("Arbiter needs a named counterparty, cannot be a party and takes at most 1000 basis points")]
+    InvalidArbitration,
+
+    // A dispute was raised on an escrow that has no arbiter
+    #[msg("Escrow has no arbiter to settle a dispute")]
+    NoArbiter,
+
+    // The escrow is frozen while a dispute is open
+    #[msg("Escrow is frozen by an open dispute")]
+    Disputed,
+
+    // A dispute was raised on an offer the taker hasn't paid anything into
+    #[msg("Only an offer the taker has partly filled can be disputed")]
+    NothingToDispute,
+
+    // The arbiter tried to resolve an escrow nobody disputed
+    #[msg("There is no open dispute to resolve")]
+    NoDispute,
+
+    // Vesting schedule timestamps are out of order or too far apart
+    #[msg("Vesting schedule must have start <= cliff <= end, start < end and a length that fits in an i64")]
+    InvalidSchedule,
+
+    // The beneficiary claimed before anything new vested
+    #[msg("Nothing has vested since the last claim")]
+    NothingToClaim,
+
+    // The offer book page picked at make time has no room left
+    #[msg("Offer book page is full, use another page")]
+    OfferBookFull,
+
+    // The escrow is missing from the offer book page it was listed on
+    #[msg("Escrow is not listed on this offer book page")]
+    OfferNotInBook,
+
+    // remaining_accounts of take_many do not describe a fillable offer of the pair
+    #[msg("Offer accounts do not match an escrow for this mint pair")]
+    InvalidOfferAccounts,
+
+    // The offer asks more token B per token A than the taker's limit
+    #[msg("Offer price is above the taker's limit")]
+    PriceTooHigh,
+
+    // Collection offers are filled with an NFT through take_collection, never with token B
+    #[msg("Offer asks for an NFT from a collection, use take_collection")]
+    CollectionOffer,
+
+    // take_collection was called on an offer that asks for token B
+    #[msg("Offer asks for token B, use take")]
+    NotCollectionOffer,
+
+    // The NFT passed to take_collection is not a verified member of the requested collection
+    #[msg("NFT is not a verified member of the requested collection")]
+    NotInCollection,
+
+    // The admin paused the program, so offers can't be made or taken
+    #[msg("The program is paused")]
+    Paused,
+
+    // The maker withdrew from the vault after the counter-offer was made
+    #[msg("Escrow vault holds less token A than the counter-offer asks for")]
+    CounterAmountUnavailable,
+
+    // A new offer book page was requested before the page in front of it exists
+    #[msg("Offer book pages must be created in order")]
+    OfferBookPageSkipped,
+
+    // The maker amended the offer before the take landed, past the taker's limits
+    #[msg("Offer terms moved past the taker's limits")]
+    SlippageExceeded,
+
+    // take_many only moves SPL tokens, so offers on native SOL must be taken one by one
+    #[msg("Native SOL offers can't be taken in a batch, use take")]
+    NativeMintUnsupported,
+
+    // Overflow occurred in a calculation
+    #[msg("Arithmetic overflow in calculation")]
+    Overflow,
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+// ===== ESCROW EVENTS =====
+// Events emitted to the program logs so clients can follow offers without polling accounts
+
+// Emitted when a maker opens a new offer
+#[event]
+pub struct OfferCreated {
+    pub escrow: Pubkey,  // The new escrow PDA
+    pub maker: Pubkey,   // The maker who opened it
+    pub seed: u64,       // Seed the escrow PDA was derived with
+    pub mint_a: Pubkey,  // Token offered by the maker
+    pub mint_b: Pubkey,  // Token (or collection) asked for in return
+    pub deposit: u64,    // Amount of token A deposited into the vault
+    pub receive: u64,    // Amount of token B asked for
+    pub expires_at: i64, // Unix timestamp after which the offer can no longer be taken
+}
+
+// Emitted on every fill, whether the offer is taken in full or in part
+#[event]
+pub struct OfferTaken {
+    pub escrow: Pubkey, // The escrow that was filled
+    pub taker: Pubkey,  // The taker who filled it
+    pub amount_a: u64,  // Amount of token A released to the taker
+    pub amount_b: u64,  // Amount of token B paid for it, protocol fee included
+    pub remaining: u64, // Amount of token B still open, zero once the escrow is closed
+}
+
+// Emitted when an offer is cancelled and the vault returned to the maker
+#[event]
+pub struct OfferRefunded {
+    pub escrow: Pubkey, // The closed escrow PDA
+    pub maker: Pubkey,  // The maker who got the deposit back
+    pub amount_a: u64,  // Amount of token A returned from the vault
+}
+
+// Emitted when the maker amends the terms of an open offer
+#[event]
+pub struct OfferUpdated {
+    pub escrow: Pubkey,  // The amended escrow PDA
+    pub maker: Pubkey,   // The maker who amended it
+    pub receive: u64,    // Total amount of token B now asked for
+    pub remaining: u64,  // Amount of token B still to be filled
+    pub deposit: u64,    // Amount of token A now held in the vault
+    pub expires_at: i64, // Expiry of the offer after the update
+}
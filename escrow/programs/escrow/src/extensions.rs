@@ -0,0 +1,121 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            transfer_fee::{TransferFeeAmount, TransferFeeConfig},
+            BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+        },
+    },
+    token_2022_extensions::{harvest_withheld_tokens_to_mint, HarvestWithheldTokensToMint},
+    token_interface::Mint,
+};
+
+use crate::error::EscrowError;
+
+// ===== TOKEN-2022 MINT EXTENSIONS =====
+// Helpers for mints owned by the Token-2022 program. Legacy SPL Token mints have
+// no extensions, so every helper is a no-op for them.
+
+// Extensions that would let someone other than the escrow move or freeze the vault,
+// or that need extra accounts on every transfer
+const UNSUPPORTED_EXTENSIONS: [ExtensionType; 3] = [
+    ExtensionType::NonTransferable,
+    ExtensionType::PermanentDelegate,
+    ExtensionType::TransferHook,
+];
+
+// Rejects mints carrying extensions the escrow cannot safely hold
+pub fn check_mint_extensions(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    let info = mint.to_account_info();
+    if *info.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+
+    let data = info.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    for extension in state.get_extension_types()? {
+        require!(
+            !UNSUPPORTED_EXTENSIONS.contains(&extension),
+            EscrowError::UnsupportedMintExtension
+        );
+    }
+
+    Ok(())
+}
+
+// Returns the amount that must be sent so the recipient nets `amount` after the
+// mint's transfer fee for the current epoch is withheld
+pub fn gross_up_for_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let info = mint.to_account_info();
+    if *info.owner != spl_token_2022::ID {
+        return Ok(amount);
+    }
+
+    let data = info.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    let Ok(fee_config) = state.get_extension::<TransferFeeConfig>() else {
+        return Ok(amount);
+    };
+
+    let epoch = Clock::get()?.epoch;
+    fee_config
+        .get_epoch_fee(epoch)
+        .calculate_pre_fee_amount(amount)
+        .ok_or(error!(EscrowError::Overflow))
+}
+
+// Returns what the recipient nets when `amount` is sent, after the mint's transfer fee
+// for the current epoch is withheld
+pub fn net_of_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let info = mint.to_account_info();
+    if *info.owner != spl_token_2022::ID {
+        return Ok(amount);
+    }
+
+    let data = info.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    let Ok(fee_config) = state.get_extension::<TransferFeeConfig>() else {
+        return Ok(amount);
+    };
+
+    let epoch = Clock::get()?.epoch;
+    fee_config
+        .get_epoch_fee(epoch)
+        .calculate_post_fee_amount(amount)
+        .ok_or(error!(EscrowError::Overflow))
+}
+
+// Moves any transfer fees withheld in `account` to the mint. Token-2022 refuses to close
+// an account that still holds withheld fees, so vaults must be harvested before they are
+// closed. Harvesting is permissionless, but the mint must be passed as writable.
+pub fn harvest_withheld_fees<'info>(
+    token_program: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+) -> Result<()> {
+    if *account.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+
+    let withheld = {
+        let data = account.try_borrow_data()?;
+        let state = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+        match state.get_extension::<TransferFeeAmount>() {
+            Ok(fee_amount) => u64::from(fee_amount.withheld_amount),
+            Err(_) => 0,
+        }
+    };
+    if withheld == 0 {
+        return Ok(());
+    }
+
+    let cpi_ctx = CpiContext::new(
+        token_program.clone(),
+        HarvestWithheldTokensToMint {
+            token_program_id: token_program,
+            mint,
+        },
+    );
+    harvest_withheld_tokens_to_mint(cpi_ctx, vec![account])
+}
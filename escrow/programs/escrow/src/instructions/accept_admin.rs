@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::EscrowError, state::Config};
+
+// ===== ACCEPT ADMIN INSTRUCTION ACCOUNTS =====
+// Second step of an admin handover: the wallet set_admin offered the role to signs to
+// take it, so the role can't be handed to a mistyped or unusable key.
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    pub new_admin: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = config.pending_admin == Some(new_admin.key()) @ EscrowError::Unauthorized,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> AcceptAdmin<'info> {
+    pub fn accept_admin(&mut self) -> Result<()> {
+        self.config.admin = self.new_admin.key();
+        self.config.pending_admin = None;
+        Ok(())
+    }
+}
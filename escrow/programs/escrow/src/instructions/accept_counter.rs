@@ -0,0 +1,371 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::spl_token::native_mint,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    error::EscrowError,
+    events::OfferTaken,
+    extensions::harvest_withheld_fees,
+    state::{Config, CounterOffer, Escrow, OfferBook},
+};
+
+// ===== ACCEPT COUNTER INSTRUCTION ACCOUNTS =====
+// The maker accepts a counter-offer: the counter vault's token B goes to the maker's
+// recipient and the token A amount pinned in the counter-offer goes to the taker, in one
+// atomic instruction. Any token A beyond that goes back to the maker. Both vaults, the
+// escrow and the counter-offer are closed. The protocol fee is taken from the counter's
+// token B, as on take.
+#[derive(Accounts)]
+pub struct AcceptCounter<'info> {
+    // The maker accepting - pays for any token accounts created on demand
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    // The taker who made the counter-offer - receives token A and the counter's rent
+    #[account(mut)]
+    pub taker: SystemAccount<'info>,
+
+    // Where the maker routes token B, must match the escrow
+    /// CHECK: Safe because the address must match the escrow and only its token B account
+    /// is written to, or lamports are sent to it when token B is native SOL
+    #[account(
+        mut,
+        address = escrow.maker_recipient
+    )]
+    pub maker_recipient: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        mint::token_program = token_program
+    )]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        mint::token_program = token_program
+    )]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    // Where the maker's recipient receives token B
+    // Left out when token B is native SOL, which is unwrapped and paid to the recipient's wallet
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker_recipient,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // Where token A beyond the counter-offer's amount goes back to the maker
+    // Only needed when the vault holds more than that, and never for native SOL
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // Where the taker receives token A
+    // Left out when token A is native SOL, which is paid into the taker's wallet
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a,
+        has_one = mint_b,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    // The offer book page listing this escrow - the entry is removed as the offer is closed
+    #[account(
+        mut,
+        seeds = [
+            b"book",
+            escrow.mint_a.as_ref(),
+            escrow.mint_b.as_ref(),
+            escrow.book_page.to_le_bytes().as_ref()
+        ],
+        bump = book.bump,
+    )]
+    pub book: Account<'info, OfferBook>,
+
+    #[account(
+        mut,
+        close = taker,
+        has_one = escrow,
+        has_one = taker,
+        seeds = [b"counter", escrow.key().as_ref(), taker.key().as_ref()],
+        bump = counter.bump,
+    )]
+    pub counter: Account<'info, CounterOffer>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = counter,
+        associated_token::token_program = token_program
+    )]
+    pub counter_vault: InterfaceAccount<'info, TokenAccount>,
+
+    // Global config holding the protocol fee and the pause switch
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    // Receives the protocol fee, must match the config
+    #[account(
+        mut,
+        address = config.fee_recipient
+    )]
+    pub fee_recipient: SystemAccount<'info>,
+
+    // Where the protocol fee in token B is paid
+    // Left out when token B is native SOL or while the fee is zero
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = mint_b,
+        associated_token::authority = fee_recipient,
+        associated_token::token_program = token_program
+    )]
+    pub fee_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AcceptCounter<'info> {
+    pub fn accept_counter(&mut self) -> Result<()> {
+        self.config.check_not_paused()?;
+        self.escrow.check_not_disputed()?;
+        require!(
+            Clock::get()?.unix_timestamp < self.escrow.expires_at,
+            EscrowError::OfferExpired
+        );
+
+        emit!(OfferTaken {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            amount_a: self.counter.amount_a,
+            amount_b: self.counter_vault.amount,
+            remaining: 0,
+        });
+
+        // 1. Counter vault token B to the maker minus the protocol fee, rent back to the taker
+        self.settle_counter_vault()?;
+
+        // 2. The counter's token A to the taker, any surplus and the vault rent to the maker
+        self.settle_escrow_vault()?;
+
+        // 3. The offer is fully filled, so it is unlisted and the escrow is closed as well
+        self.book.remove(&self.escrow.key())?;
+        self.escrow.close(self.maker.to_account_info())
+    }
+
+    fn settle_counter_vault(&self) -> Result<()> {
+        let escrow_key = self.escrow.key();
+        let taker_key = self.taker.key();
+        let seeds = &[
+            b"counter",
+            escrow_key.as_ref(),
+            taker_key.as_ref(),
+            &[self.counter.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let amount_b = self.counter_vault.amount;
+        let fee = self.config.fee_for(amount_b)?;
+
+        if native_mint::check_id(&self.mint_b.key()) {
+            // Closing the wSOL vault to the maker unwraps the SOL straight into their wallet;
+            // the maker then forwards it to their recipient, hands the vault's rent back to
+            // the taker who paid it, and the protocol fee to the fee recipient
+            let rent = self
+                .counter_vault
+                .to_account_info()
+                .lamports()
+                .checked_sub(amount_b)
+                .ok_or(EscrowError::Overflow)?;
+            self.close_counter_vault(self.maker.to_account_info(), signer_seeds)?;
+
+            self.pay_from_maker(self.taker.to_account_info(), rent)?;
+            if self.maker_recipient.key() != self.maker.key() {
+                self.pay_from_maker(self.maker_recipient.to_account_info(), amount_b - fee)?;
+            }
+            if fee > 0 {
+                self.pay_from_maker(self.fee_recipient.to_account_info(), fee)?;
+            }
+            return Ok(());
+        }
+
+        // Any Token-2022 transfer fee on the way out is withheld from what the maker receives
+        let maker_ata_b = self.maker_ata_b.as_ref().map(|ata| ata.to_account_info());
+        self.pay_from_counter_vault(maker_ata_b, amount_b - fee, signer_seeds)?;
+        if fee > 0 {
+            let fee_ata_b = self.fee_ata_b.as_ref().map(|ata| ata.to_account_info());
+            self.pay_from_counter_vault(fee_ata_b, fee, signer_seeds)?;
+        }
+
+        self.close_counter_vault(self.taker.to_account_info(), signer_seeds)
+    }
+
+    fn pay_from_counter_vault(
+        &self,
+        to: Option<AccountInfo<'info>>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let to = to.ok_or(EscrowError::MissingTokenAccount)?;
+
+        let transfer_accounts = TransferChecked {
+            from: self.counter_vault.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to,
+            authority: self.counter.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            transfer_accounts,
+            signer_seeds,
+        );
+        transfer_checked(cpi_ctx, amount, self.mint_b.decimals)
+    }
+
+    fn pay_from_maker(&self, to: AccountInfo<'info>, lamports: u64) -> Result<()> {
+        let transfer_accounts = Transfer {
+            from: self.maker.to_account_info(),
+            to,
+        };
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+        transfer(cpi_ctx, lamports)
+    }
+
+    fn settle_escrow_vault(&self) -> Result<()> {
+        let maker_key = self.maker.key();
+        let escrow_seed = self.escrow.seed.to_le_bytes();
+        let seeds = &[
+            b"escrow",
+            maker_key.as_ref(),
+            &escrow_seed[..],
+            &[self.escrow.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        // The maker may have withdrawn from the vault since the counter was made
+        let amount_a = self.counter.amount_a;
+        let surplus = self
+            .vault
+            .amount
+            .checked_sub(amount_a)
+            .ok_or(EscrowError::CounterAmountUnavailable)?;
+
+        // Native SOL: closing the vault unwraps everything to the maker, who then pays the taker
+        if !native_mint::check_id(&self.mint_a.key()) {
+            self.pay_from_vault(&self.taker_ata_a, amount_a, signer_seeds)?;
+            if surplus > 0 {
+                self.pay_from_vault(&self.maker_ata_a, surplus, signer_seeds)?;
+            }
+        }
+
+        harvest_withheld_fees(
+            self.token_program.to_account_info(),
+            self.mint_a.to_account_info(),
+            self.vault.to_account_info(),
+        )?;
+        let close_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.maker.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            close_accounts,
+            signer_seeds,
+        );
+        close_account(cpi_ctx)?;
+
+        if native_mint::check_id(&self.mint_a.key()) {
+            self.pay_from_maker(self.taker.to_account_info(), amount_a)?;
+        }
+
+        Ok(())
+    }
+
+    fn pay_from_vault(
+        &self,
+        to: &Option<InterfaceAccount<'info, TokenAccount>>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let to = to.as_ref().ok_or(EscrowError::MissingTokenAccount)?;
+
+        let transfer_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            to: to.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            transfer_accounts,
+            signer_seeds,
+        );
+        transfer_checked(cpi_ctx, amount, self.mint_a.decimals)
+    }
+
+    fn close_counter_vault(
+        &self,
+        destination: AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        harvest_withheld_fees(
+            self.token_program.to_account_info(),
+            self.mint_b.to_account_info(),
+            self.counter_vault.to_account_info(),
+        )?;
+        let close_accounts = CloseAccount {
+            account: self.counter_vault.to_account_info(),
+            destination,
+            authority: self.counter.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            close_accounts,
+            signer_seeds,
+        );
+        close_account(cpi_ctx)
+    }
+}
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::EscrowError, state::MilestoneEscrow};
+
+// ===== APPROVE MILESTONE REFUND INSTRUCTION ACCOUNTS =====
+// The payer or the payee signs off on unwinding a milestone escrow. Once both have,
+// the payer can refund every unreleased tranche before the deadline.
+#[derive(Accounts)]
+pub struct ApproveMilestoneRefund<'info> {
+    // The payer or the payee
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"milestone",
+            milestone_escrow.payer.as_ref(),
+            milestone_escrow.seed.to_le_bytes().as_ref()
+        ],
+        bump = milestone_escrow.bump,
+    )]
+    pub milestone_escrow: Account<'info, MilestoneEscrow>,
+}
+
+impl<'info> ApproveMilestoneRefund<'info> {
+    pub fn approve_refund(&mut self) -> Result<()> {
+        let signer = self.signer.key();
+        if signer == self.milestone_escrow.payer {
+            self.milestone_escrow.payer_approved_refund = true;
+        } else if signer == self.milestone_escrow.payee {
+            self.milestone_escrow.payee_approved_refund = true;
+        } else {
+            return err!(EscrowError::Unauthorized);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::NONCES_PER_PAGE, state::OrderNonces};
+
+// ===== CANCEL ORDER INSTRUCTION ACCOUNTS =====
+// Lets a maker invalidate a signed order before anyone fills it by burning its nonce
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct CancelOrder<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    // The page of the maker's nonce bitmap covering this nonce
+    #[account(
+        init_if_needed,
+        payer = maker,
+        seeds = [
+            b"nonces",
+            maker.key().as_ref(),
+            (nonce / NONCES_PER_PAGE).to_le_bytes().as_ref()
+        ],
+        bump,
+        space = 8 + OrderNonces::INIT_SPACE
+    )]
+    pub nonces: Account<'info, OrderNonces>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelOrder<'info> {
+    pub fn cancel_order(&mut self, nonce: u64, bumps: &CancelOrderBumps) -> Result<()> {
+        if self.nonces.maker == Pubkey::default() {
+            self.nonces.maker = self.maker.key();
+            self.nonces.page = nonce / NONCES_PER_PAGE;
+            self.nonces.bump = bumps.nonces;
+        }
+
+        // Fails if the order was already filled or cancelled
+        self.nonces.consume(nonce)
+    }
+}
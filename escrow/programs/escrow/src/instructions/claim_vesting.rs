@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{error::EscrowError, extensions::harvest_withheld_fees, state::Vesting};
+
+// ===== CLAIM VESTING INSTRUCTION ACCOUNTS =====
+// The beneficiary claims everything vested so far. The final claim closes the vault
+// and the vesting account, with the rent going back to the funder.
+#[derive(Accounts)]
+pub struct ClaimVesting<'info> {
+    // The beneficiary - pays for beneficiary_ata if it has to be created
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+
+    // Gets the rent back once everything is claimed
+    #[account(mut)]
+    pub funder: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        mint::token_program = token_program
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        associated_token::mint = mint,
+        associated_token::authority = beneficiary,
+        associated_token::token_program = token_program
+    )]
+    pub beneficiary_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        has_one = funder,
+        has_one = beneficiary,
+        has_one = mint,
+        seeds = [b"vesting", funder.key().as_ref(), vesting.seed.to_le_bytes().as_ref()],
+        bump = vesting.bump,
+    )]
+    pub vesting: Account<'info, Vesting>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vesting,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimVesting<'info> {
+    pub fn claim(&mut self) -> Result<()> {
+        let vested = self.vesting.vested(Clock::get()?.unix_timestamp)?;
+        let mut amount = vested - self.vesting.claimed;
+        require!(amount > 0, EscrowError::NothingToClaim);
+        self.vesting.claimed = vested;
+
+        // The final claim empties the vault, so tokens anyone sent to it can't block the close
+        let done = self.vesting.claimed == self.vesting.total;
+        if done {
+            amount = self.vault.amount;
+        }
+
+        let funder_key = self.funder.key();
+        let vesting_seed = self.vesting.seed.to_le_bytes();
+        let seeds = &[
+            b"vesting",
+            funder_key.as_ref(),
+            &vesting_seed[..],
+            &[self.vesting.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let transfer_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.beneficiary_ata.to_account_info(),
+            authority: self.vesting.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            transfer_accounts,
+            signer_seeds,
+        );
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+
+        if !done {
+            return Ok(());
+        }
+
+        // Fully vested and claimed, so the schedule is done
+        harvest_withheld_fees(
+            self.token_program.to_account_info(),
+            self.mint.to_account_info(),
+            self.vault.to_account_info(),
+        )?;
+        let close_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.funder.to_account_info(),
+            authority: self.vesting.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            close_accounts,
+            signer_seeds,
+        );
+        close_account(cpi_ctx)?;
+
+        self.vesting.close(self.funder.to_account_info())
+    }
+}
@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::spl_token::native_mint,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{error::EscrowError, extensions::harvest_withheld_fees, state::CounterOffer};
+
+// ===== CLOSE COUNTER INSTRUCTION ACCOUNTS =====
+// Shared by reject_counter (signed by the maker) and withdraw_counter (signed by the taker).
+// Either way the proposed token B goes back to the taker and the counter-offer is closed.
+// The escrow itself is not needed, so a counter can be withdrawn after the escrow is gone.
+#[derive(Accounts)]
+pub struct CloseCounter<'info> {
+    // The maker rejecting or the taker withdrawing - pays for taker_ata_b if it has to be created
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    // The taker who made the counter-offer - gets the token B and all rent back
+    #[account(mut)]
+    pub taker: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        mint::token_program = token_program
+    )]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    // Where the taker gets token B back
+    // Left out when token B is native SOL, which is unwrapped into the taker's wallet
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        close = taker,
+        has_one = taker,
+        has_one = mint_b,
+        seeds = [b"counter", counter.escrow.as_ref(), taker.key().as_ref()],
+        bump = counter.bump,
+    )]
+    pub counter: Account<'info, CounterOffer>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = counter,
+        associated_token::token_program = token_program
+    )]
+    pub counter_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CloseCounter<'info> {
+    // The maker turns the counter-offer down
+    pub fn reject(&mut self) -> Result<()> {
+        require_keys_eq!(
+            self.signer.key(),
+            self.counter.maker,
+            EscrowError::Unauthorized
+        );
+        self.return_to_taker()
+    }
+
+    // The taker pulls an unaccepted counter-offer
+    pub fn withdraw(&mut self) -> Result<()> {
+        require_keys_eq!(
+            self.signer.key(),
+            self.counter.taker,
+            EscrowError::Unauthorized
+        );
+        self.return_to_taker()
+    }
+
+    fn return_to_taker(&mut self) -> Result<()> {
+        let escrow_key = self.counter.escrow;
+        let taker_key = self.taker.key();
+        let seeds = &[
+            b"counter",
+            escrow_key.as_ref(),
+            taker_key.as_ref(),
+            &[self.counter.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        // Closing a wSOL vault already hands its whole balance to the taker as SOL
+        if !native_mint::check_id(&self.mint_b.key()) {
+            let taker_ata_b = self
+                .taker_ata_b
+                .as_ref()
+                .ok_or(EscrowError::MissingTokenAccount)?;
+
+            let transfer_accounts = TransferChecked {
+                from: self.counter_vault.to_account_info(),
+                mint: self.mint_b.to_account_info(),
+                to: taker_ata_b.to_account_info(),
+                authority: self.counter.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                transfer_accounts,
+                signer_seeds,
+            );
+            transfer_checked(cpi_ctx, self.counter_vault.amount, self.mint_b.decimals)?;
+        }
+
+        harvest_withheld_fees(
+            self.token_program.to_account_info(),
+            self.mint_b.to_account_info(),
+            self.counter_vault.to_account_info(),
+        )?;
+        let close_accounts = CloseAccount {
+            account: self.counter_vault.to_account_info(),
+            destination: self.taker.to_account_info(),
+            authority: self.counter.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            close_accounts,
+            signer_seeds,
+        );
+        close_account(cpi_ctx)
+    }
+}
@@ -0,0 +1,257 @@
+use anchor_lang::{prelude::*, solana_program::ed25519_program};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+use solana_instructions_sysvar::{load_current_index_checked, load_instruction_at_checked};
+
+use crate::{
+    constants::NONCES_PER_PAGE,
+    error::EscrowError,
+    extensions::{check_mint_extensions, gross_up_for_fee},
+    state::{Config, OrderNonces, SignedOrder},
+};
+
+// ===== FILL ORDER INSTRUCTION ACCOUNTS =====
+// Settles an order the maker signed off-chain. The transaction must carry an Ed25519
+// program instruction right before this one that verifies the maker's signature over
+// `order.message()`. Token A moves straight from the maker's token account using the
+// order authority PDA, which the maker approved as delegate beforehand. Each side covers
+// the Token-2022 transfer fee on what it sends, so the maker's allowance must include the
+// fee on token A.
+#[derive(Accounts)]
+#[instruction(order: SignedOrder)]
+pub struct FillOrder<'info> {
+    // The taker settling the order - pays for any accounts created on demand
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    // The maker who signed the order (not a signer in this transaction)
+    #[account(address = order.maker)]
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        address = order.mint_a,
+        mint::token_program = token_program
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        address = order.mint_b,
+        mint::token_program = token_program
+    )]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    // Source of token A, with the order authority approved as delegate
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Where the maker receives token B
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Where the taker receives token A
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Source of the taker's token B
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // PDA makers approve as delegate on their token A accounts
+    /// CHECK: Only used as the signing delegate for the token A transfer
+    #[account(
+        seeds = [b"order_authority"],
+        bump
+    )]
+    pub order_authority: UncheckedAccount<'info>,
+
+    // The page of the maker's nonce bitmap covering this order's nonce
+    #[account(
+        init_if_needed,
+        payer = taker,
+        seeds = [
+            b"nonces",
+            order.maker.as_ref(),
+            (order.nonce / NONCES_PER_PAGE).to_le_bytes().as_ref()
+        ],
+        bump,
+        space = 8 + OrderNonces::INIT_SPACE
+    )]
+    pub nonces: Box<Account<'info, OrderNonces>>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(address = config.fee_recipient)]
+    pub fee_recipient: SystemAccount<'info>,
+
+    // Where the protocol fee in token B is paid
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = fee_recipient,
+        associated_token::token_program = token_program
+    )]
+    pub fee_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Instructions sysvar, used to find the Ed25519 signature check
+    /// CHECK: Address is checked against the instructions sysvar id
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> FillOrder<'info> {
+    pub fn fill_order(&mut self, order: &SignedOrder, bumps: &FillOrderBumps) -> Result<()> {
+        self.config.check_not_paused()?;
+        require!(
+            Clock::get()?.unix_timestamp < order.expires_at,
+            EscrowError::OfferExpired
+        );
+        require!(
+            order.amount_a > 0 && order.amount_b > 0,
+            EscrowError::InvalidAmount
+        );
+        // Signed orders follow the same mint policy as offers made on-chain
+        check_mint_extensions(&self.mint_a)?;
+        check_mint_extensions(&self.mint_b)?;
+
+        // 1. The maker must have signed exactly this order
+        self.verify_signature(order)?;
+
+        // 2. Burn the nonce so the same signature can't be settled twice
+        if self.nonces.maker == Pubkey::default() {
+            self.nonces.maker = order.maker;
+            self.nonces.page = order.nonce / NONCES_PER_PAGE;
+            self.nonces.bump = bumps.nonces;
+        }
+        self.nonces.consume(order.nonce)?;
+
+        // 3. Token B from the taker to the maker, minus the protocol fee
+        let fee = self.config.fee_for(order.amount_b)?;
+        self.transfer_b(&self.maker_ata_b, order.amount_b - fee)?;
+        if fee > 0 {
+            self.transfer_b(&self.fee_ata_b, fee)?;
+        }
+
+        // 4. Token A from the maker to the taker, signed by the delegated order authority
+        self.transfer_a_to_taker(order.amount_a, bumps.order_authority)
+    }
+
+    // Checks that the previous instruction is an Ed25519 program instruction verifying
+    // one signature by the maker over the order message, with all data inline
+    fn verify_signature(&self, order: &SignedOrder) -> Result<()> {
+        let instructions = self.instructions.to_account_info();
+        let current = load_current_index_checked(&instructions)?;
+        require!(current > 0, EscrowError::InvalidOrderSignature);
+        let ix = load_instruction_at_checked(current as usize - 1, &instructions)?;
+
+        require_keys_eq!(
+            ix.program_id,
+            ed25519_program::ID,
+            EscrowError::InvalidOrderSignature
+        );
+        require!(ix.accounts.is_empty(), EscrowError::InvalidOrderSignature);
+
+        // Layout: [num_signatures: u8, padding: u8, offsets: 7 x u16, ...data]
+        let data = &ix.data;
+        require!(
+            data.len() >= 16 && data[0] == 1,
+            EscrowError::InvalidOrderSignature
+        );
+        let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+        let public_key_offset = read_u16(6) as usize;
+        let message_offset = read_u16(10) as usize;
+        let message_size = read_u16(12) as usize;
+
+        // Every offset must point into this same instruction (index u16::MAX)
+        require!(
+            read_u16(4) == u16::MAX && read_u16(8) == u16::MAX && read_u16(14) == u16::MAX,
+            EscrowError::InvalidOrderSignature
+        );
+
+        let public_key = data
+            .get(public_key_offset..public_key_offset + 32)
+            .ok_or(EscrowError::InvalidOrderSignature)?;
+        let message = data
+            .get(message_offset..message_offset + message_size)
+            .ok_or(EscrowError::InvalidOrderSignature)?;
+
+        require!(
+            public_key == order.maker.as_ref(),
+            EscrowError::InvalidOrderSignature
+        );
+        require!(
+            message == order.message()?.as_slice(),
+            EscrowError::InvalidOrderSignature
+        );
+
+        Ok(())
+    }
+
+    // Transfer token B from the taker, with the taker covering any Token-2022 transfer fee
+    fn transfer_b(&self, to: &InterfaceAccount<'info, TokenAccount>, amount: u64) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to: to.to_account_info(),
+            authority: self.taker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+
+        let gross_amount = gross_up_for_fee(&self.mint_b, amount)?;
+        transfer_checked(cpi_ctx, gross_amount, self.mint_b.decimals)
+    }
+
+    // Transfer token A from the maker using the order authority's delegated allowance,
+    // with the maker covering any Token-2022 transfer fee so the taker nets `amount`
+    fn transfer_a_to_taker(&self, amount: u64, bump: u8) -> Result<()> {
+        let seeds: &[&[u8]] = &[b"order_authority", &[bump]];
+        let signer_seeds = &[seeds];
+
+        let transfer_accounts = TransferChecked {
+            from: self.maker_ata_a.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            to: self.taker_ata_a.to_account_info(),
+            authority: self.order_authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            transfer_accounts,
+            signer_seeds,
+        );
+
+        let gross_amount = gross_up_for_fee(&self.mint_a, amount)?;
+        transfer_checked(cpi_ctx, gross_amount, self.mint_a.decimals)
+    }
+}
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::MAX_FEE_BPS, error::EscrowError, program::Escrow, state::Config};
+
+// ===== INITIALIZE CONFIG INSTRUCTION ACCOUNTS =====
+// Creates the global config PDA. It can only be created once, and only by the program's
+// upgrade authority, who becomes the admin, so nobody can claim the fee by calling it first.
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    // This program, to look up its program data account
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, Escrow>,
+
+    // Holds the upgrade authority, which must be the signer
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ EscrowError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"config"],
+        bump,
+        space = 8 + Config::INIT_SPACE
+    )]
+    pub config: Account<'info, Config>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeConfig<'info> {
+    pub fn initialize_config(
+        &mut self,
+        fee_bps: u16,
+        fee_recipient: Pubkey,
+        bumps: &InitializeConfigBumps,
+    ) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, EscrowError::InvalidFee);
+
+        self.config.set_inner(Config {
+            admin: self.admin.key(),
+            pending_admin: None,
+            fee_bps,
+            fee_recipient,
+            paused: false,
+            bump: bumps.config,
+        });
+        Ok(())
+    }
+}
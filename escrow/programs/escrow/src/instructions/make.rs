@@ -1,13 +1,18 @@
-use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token_interface::{TokenAccount, TokenInterface, Mint, TransferChecked, transfer_checked}};
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::{associated_token::AssociatedToken, token::spl_token::native_mint, token_interface::{TokenAccount, TokenInterface, Mint, TransferChecked, transfer_checked, SyncNative, sync_native}};
 
-use crate::state::Escrow;
+use crate::{
+    error::EscrowError,
+    events::OfferCreated,
+    extensions::{check_mint_extensions, gross_up_for_fee},
+    state::{ArbiterTerms, Arbitration, Config, Escrow, OfferBook},
+};
 
 // ===== MAKE INSTRUCTION ACCOUNTS =====
 // This struct defines all the accounts needed for the 'make' instruction
 // which creates a new escrow offer from the maker
 #[derive(Accounts)]
-#[instruction(seed: u64)] // This makes the instruction arguments available for account constraints
+#[instruction(seed: u64, book_page: u32)] // This makes the instruction arguments available for account constraints
 pub struct Make <'info> {
     // The maker is the account creating the escrow offer
     // They must sign the transaction and will pay for account creation
@@ -28,13 +33,14 @@ pub struct Make <'info> {
 
     // The maker's associated token account for token A
     // This is where the tokens being offered will come from
+    // Left out when offering native SOL, which is wrapped straight into the vault
     #[account(
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = maker,
         associated_token::token_program = token_program
     )]
-    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
 
     // The escrow state account (PDA) that stores trade information
     // We create this account using a seed derived from the maker's key and a nonce
@@ -59,6 +65,43 @@ pub struct Make <'info> {
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
 
+    // The offer book page that lists this escrow so takers can find it
+    // Created on demand the first time an offer for the pair lands on this page
+    #[account(
+        init_if_needed,
+        payer = maker,
+        seeds = [
+            b"book",
+            mint_a.key().as_ref(),
+            mint_b.key().as_ref(),
+            book_page.to_le_bytes().as_ref()
+        ],
+        bump,
+        space = 8 + OfferBook::INIT_SPACE
+    )]
+    pub book: Account<'info, OfferBook>,
+
+    // The page before `book`, which must already exist when `book` is created
+    // Pages are created in order so takers can stop at the first missing one
+    // Left out on page 0, or when `book` already exists
+    #[account(
+        seeds = [
+            b"book",
+            mint_a.key().as_ref(),
+            mint_b.key().as_ref(),
+            book_page.saturating_sub(1).to_le_bytes().as_ref()
+        ],
+        bump = prev_book.bump,
+    )]
+    pub prev_book: Option<Account<'info, OfferBook>>,
+
+    // Global config, checked so no offer is made while the program is paused
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
     // Required programs
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
@@ -68,27 +111,114 @@ pub struct Make <'info> {
 // Implementation of the Make instruction functionality
 impl<'info> Make<'info> {
     // Initialize the escrow state data with trade parameters
-    pub fn init_escrow(&mut self, seed: u64, receive: u64, bumps: &MakeBumps) -> Result<()> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn init_escrow(
+        &mut self,
+        seed: u64,
+        book_page: u32,
+        receive: u64,
+        collection: bool,
+        expires_at: i64,
+        taker: Option<Pubkey>,
+        allowlist: Option<[u8; 32]>,
+        arbitration: Option<ArbiterTerms>,
+        maker_recipient: Option<Pubkey>,
+        bumps: &MakeBumps,
+    ) -> Result<()> {
+        self.config.check_not_paused()?;
+        // An offer asking for nothing could be taken for free
+        require!(receive > 0, EscrowError::InvalidAmount);
+        // A collection offer is filled by exactly one NFT from it
+        require!(!collection || receive == 1, EscrowError::InvalidAmount);
+        // An offer that is already expired could never be taken
+        require!(
+            expires_at > Clock::get()?.unix_timestamp,
+            EscrowError::InvalidExpiry
+        );
+        // Non-transferable, permanent-delegate and transfer-hook mints can't be escrowed safely
+        check_mint_extensions(&self.mint_a)?;
+        check_mint_extensions(&self.mint_b)?;
+        // A dispute needs a known counterparty, so an arbiter is only allowed with a named taker
+        let arbitration = match arbitration {
+            Some(terms) => {
+                let taker = taker.ok_or(EscrowError::InvalidArbitration)?;
+                Some(Arbitration::new(terms, [self.maker.key(), taker])?)
+            }
+            None => None,
+        };
+        // Proceeds go to the maker unless they route them elsewhere, e.g. to a cold wallet
+        let maker_recipient = maker_recipient.unwrap_or(self.maker.key());
+
         // Store all the escrow details in the escrow account
         self.escrow.set_inner(Escrow {
             seed,                      // Random seed for PDA derivation
             maker: self.maker.key(),   // Maker's public key for ownership verification
+            maker_recipient,           // Wallet paid token B on every fill
             mint_a: self.mint_a.key(), // Token A mint (what maker is offering)
             mint_b: self.mint_b.key(), // Token B mint (what maker wants in return)
+            collection,                // Whether mint_b is a collection rather than a token
             receive,                   // Amount of token B expected in return
+            remaining: receive,        // Nothing has been filled yet
+            filled: 0,                 // No token B has been paid to the maker yet
+            expires_at,                // After this the offer can only be refunded
+            taker,                     // Optional named counterparty
+            allowlist,                 // Optional Merkle root of allowed takers
+            arbitration,               // Optional arbiter for disputes with the named taker
+            book_page,                 // Offer book page listing the escrow
             bump: bumps.escrow,        // Bump seed for the escrow PDA
         });
         Ok(())
     }
 
+    // Add the escrow to its offer book page, setting the page up if this is its first offer
+    pub fn list_offer(&mut self, bumps: &MakeBumps) -> Result<()> {
+        if self.book.mint_a == Pubkey::default() {
+            require!(
+                self.escrow.book_page == 0 || self.prev_book.is_some(),
+                EscrowError::OfferBookPageSkipped
+            );
+            self.book.set_inner(OfferBook {
+                mint_a: self.mint_a.key(),
+                mint_b: self.mint_b.key(),
+                page: self.escrow.book_page,
+                offers: Vec::new(),
+                bump: bumps.book,
+            });
+        }
+        self.book.insert(self.escrow.key())
+    }
+
     // Deposit tokens from maker's account to the escrow vault
     pub fn deposit(&mut self, deposit: u64) -> Result<()> {
+        require!(deposit > 0, EscrowError::InvalidAmount);
+
+        emit!(OfferCreated {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            seed: self.escrow.seed,
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            deposit,
+            receive: self.escrow.receive,
+            expires_at: self.escrow.expires_at,
+        });
+
+        // Native SOL is wrapped by sending lamports to the wSOL vault and syncing its balance
+        if native_mint::check_id(&self.mint_a.key()) {
+            return self.deposit_sol(deposit);
+        }
+
+        let maker_ata_a = self
+            .maker_ata_a
+            .as_ref()
+            .ok_or(EscrowError::MissingTokenAccount)?;
+
         // Set up the CPI (Cross-Program Invocation) to the token program
         let cpi_program = self.token_program.to_account_info();
         
         // Configure the transfer accounts for token A
         let transfer_accounts = TransferChecked {
-            from: self.maker_ata_a.to_account_info(),    // Source: maker's token A account
+            from: maker_ata_a.to_account_info(),         // Source: maker's token A account
             mint: self.mint_a.to_account_info(),         // Token mint for verification
             to: self.vault.to_account_info(),            // Destination: escrow vault
             authority: self.maker.to_account_info()      // Signer: maker authorizes the transfer
@@ -97,7 +227,28 @@ impl<'info> Make<'info> {
         // Create the CPI context (no signing needed since maker is a direct signer)
         let cpi_ctx = CpiContext::new(cpi_program, transfer_accounts);
         
+        // Token-2022 transfer fees are added on top so the vault holds the full deposit
+        let amount = gross_up_for_fee(&self.mint_a, deposit)?;
+
         // Execute the token transfer, specifying the amount and decimal places
-        transfer_checked(cpi_ctx, deposit, self.mint_a.decimals)
+        transfer_checked(cpi_ctx, amount, self.mint_a.decimals)
+    }
+
+    // Wrap the maker's SOL into the wSOL vault
+    fn deposit_sol(&mut self, deposit: u64) -> Result<()> {
+        // Move the lamports from the maker's wallet into the vault token account
+        let transfer_accounts = Transfer {
+            from: self.maker.to_account_info(),
+            to: self.vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+        transfer(cpi_ctx, deposit)?;
+
+        // Let the token program count the new lamports as the vault's token balance
+        let sync_accounts = SyncNative {
+            account: self.vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), sync_accounts);
+        sync_native(cpi_ctx)
     }
 }
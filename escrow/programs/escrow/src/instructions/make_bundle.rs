@@ -0,0 +1,178 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{
+        create, get_associated_token_address_with_program_id, AssociatedToken, Create,
+    },
+    token_interface::{transfer_checked, Mint, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    constants::MAX_BUNDLE_LEGS,
+    error::EscrowError,
+    extensions::{check_mint_extensions, gross_up_for_fee},
+    state::{Bundle, Config, Leg},
+};
+
+// ===== MAKE BUNDLE INSTRUCTION ACCOUNTS =====
+// Creates a bundle offer: the maker escrows up to MAX_BUNDLE_LEGS mints and asks
+// for up to MAX_BUNDLE_LEGS mints in return.
+//
+// Every leg, offered or requested, moves through the one token program passed in, so a
+// bundle can't mix SPL Token and Token-2022 mints. Mints of the other program are
+// rejected here rather than leaving a bundle nobody can take.
+//
+// remaining_accounts holds one triple per offered leg, in the same order as `offered`,
+// then the mint of every requested leg, in the same order as `requested`:
+//   offered:   [mint, maker_ata, vault]
+//   requested: [mint]
+// where vault is the bundle PDA's associated token account for the mint, created here.
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeBundle<'info> {
+    // The maker creates the bundle and pays for the bundle account and its vaults
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    // The bundle state account (PDA) that stores every leg of the trade
+    #[account(
+        init,
+        payer = maker,
+        seeds = [b"bundle", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + Bundle::INIT_SPACE
+    )]
+    pub bundle: Account<'info, Bundle>,
+
+    // Global config, checked so no bundle is made while the program is paused
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    // Required programs
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeBundle<'info> {
+    // Store the legs of the bundle after checking they are well formed
+    pub fn init_bundle(
+        &mut self,
+        seed: u64,
+        offered: Vec<Leg>,
+        requested: Vec<Leg>,
+        bumps: &MakeBundleBumps,
+    ) -> Result<()> {
+        self.config.check_not_paused()?;
+        check_legs(&offered)?;
+        check_legs(&requested)?;
+
+        self.bundle.set_inner(Bundle {
+            seed,
+            maker: self.maker.key(),
+            offered,
+            requested,
+            bump: bumps.bundle,
+        });
+        Ok(())
+    }
+
+    // Create a vault for every offered leg and move the maker's tokens into it,
+    // after checking the requested mints can be paid through the same token program
+    pub fn deposit_legs(&mut self, remaining: &'info [AccountInfo<'info>]) -> Result<()> {
+        let offered_accounts = self.bundle.offered.len() * 3;
+        require!(
+            remaining.len() == offered_accounts + self.bundle.requested.len(),
+            EscrowError::InvalidBundleAccounts
+        );
+        let (offered, requested) = remaining.split_at(offered_accounts);
+
+        for (leg, mint_info) in self.bundle.requested.iter().zip(requested) {
+            self.load_mint(mint_info, &leg.mint)?;
+        }
+
+        for (leg, accounts) in self.bundle.offered.iter().zip(offered.chunks(3)) {
+            let [mint_info, maker_ata, vault] = accounts else {
+                return err!(EscrowError::InvalidBundleAccounts);
+            };
+            let mint = self.load_mint(mint_info, &leg.mint)?;
+
+            // Vault: the bundle PDA's ATA for this mint
+            require_keys_eq!(
+                vault.key(),
+                get_associated_token_address_with_program_id(
+                    &self.bundle.key(),
+                    &leg.mint,
+                    &self.token_program.key()
+                ),
+                EscrowError::InvalidBundleAccounts
+            );
+            let create_accounts = Create {
+                payer: self.maker.to_account_info(),
+                associated_token: vault.clone(),
+                authority: self.bundle.to_account_info(),
+                mint: mint_info.clone(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            };
+            create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                create_accounts,
+            ))?;
+
+            // Token-2022 transfer fees are added on top so each vault holds the full leg amount
+            let transfer_accounts = TransferChecked {
+                from: maker_ata.clone(),
+                mint: mint_info.clone(),
+                to: vault.clone(),
+                authority: self.maker.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+            transfer_checked(cpi_ctx, gross_up_for_fee(&mint, leg.amount)?, mint.decimals)?;
+        }
+
+        Ok(())
+    }
+
+    // Check a leg's mint belongs to the bundle's token program and carries no extension
+    // the escrow can't handle, then deserialize it
+    fn load_mint(
+        &self,
+        mint_info: &'info AccountInfo<'info>,
+        expected: &Pubkey,
+    ) -> Result<InterfaceAccount<'info, Mint>> {
+        require_keys_eq!(
+            mint_info.key(),
+            *expected,
+            EscrowError::InvalidBundleAccounts
+        );
+        require_keys_eq!(
+            *mint_info.owner,
+            self.token_program.key(),
+            EscrowError::InvalidBundleAccounts
+        );
+        let mint = InterfaceAccount::<Mint>::try_from(mint_info)?;
+        check_mint_extensions(&mint)?;
+        Ok(mint)
+    }
+}
+
+// Each side needs between 1 and MAX_BUNDLE_LEGS legs, with non-zero amounts and no repeated mint
+fn check_legs(legs: &[Leg]) -> Result<()> {
+    require!(
+        !legs.is_empty() && legs.len() <= MAX_BUNDLE_LEGS as usize,
+        EscrowError::InvalidBundleLegs
+    );
+
+    for (i, leg) in legs.iter().enumerate() {
+        require!(leg.amount > 0, EscrowError::InvalidAmount);
+        require!(
+            legs[..i].iter().all(|other| other.mint != leg.mint),
+            EscrowError::InvalidBundleLegs
+        );
+    }
+
+    Ok(())
+}
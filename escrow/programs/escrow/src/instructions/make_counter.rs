@@ -0,0 +1,151 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::spl_token::native_mint,
+    token_interface::{
+        sync_native, transfer_checked, Mint, SyncNative, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    error::EscrowError,
+    extensions::gross_up_for_fee,
+    state::{Config, CounterOffer, Escrow},
+};
+
+// ===== MAKE COUNTER INSTRUCTION ACCOUNTS =====
+// A taker proposes a different amount of token B for a fixed amount of token A,
+// escrowing that token B in a counter vault until the maker answers. The token A amount
+// is pinned so the maker can't shrink the vault before accepting.
+#[derive(Accounts)]
+pub struct MakeCounter<'info> {
+    // The taker proposing the counter-offer - pays for the counter account and its vault
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    #[account(
+        mint::token_program = token_program
+    )]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    // Source of the proposed token B
+    // Left out when token B is native SOL, which is wrapped straight into the counter vault
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // The escrow being countered
+    #[account(
+        has_one = mint_b,
+        seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    // One open counter-offer per taker and escrow
+    #[account(
+        init,
+        payer = taker,
+        seeds = [b"counter", escrow.key().as_ref(), taker.key().as_ref()],
+        bump,
+        space = 8 + CounterOffer::INIT_SPACE
+    )]
+    pub counter: Account<'info, CounterOffer>,
+
+    // Holds the proposed token B, owned by the counter PDA
+    #[account(
+        init,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = counter,
+        associated_token::token_program = token_program
+    )]
+    pub counter_vault: InterfaceAccount<'info, TokenAccount>,
+
+    // Global config, checked so no counter-offer is made while the program is paused
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeCounter<'info> {
+    // - amount_a: Amount of token A asked for, usually everything left in the escrow vault
+    // - amount_b: Amount of token B offered for it
+    pub fn make_counter(
+        &mut self,
+        amount_a: u64,
+        amount_b: u64,
+        bumps: &MakeCounterBumps,
+    ) -> Result<()> {
+        self.config.check_not_paused()?;
+        self.escrow.check_not_disputed()?;
+        self.escrow.check_not_collection()?;
+        require!(amount_a > 0 && amount_b > 0, EscrowError::InvalidAmount);
+        require!(
+            Clock::get()?.unix_timestamp < self.escrow.expires_at,
+            EscrowError::OfferExpired
+        );
+
+        self.counter.set_inner(CounterOffer {
+            escrow: self.escrow.key(),
+            maker: self.escrow.maker,
+            taker: self.taker.key(),
+            mint_b: self.mint_b.key(),
+            amount_a,
+            amount_b,
+            bump: bumps.counter,
+        });
+
+        self.deposit(amount_b)
+    }
+
+    // Move the proposed token B into the counter vault
+    fn deposit(&mut self, amount_b: u64) -> Result<()> {
+        // Native SOL is wrapped by sending lamports to the wSOL vault and syncing its balance
+        if native_mint::check_id(&self.mint_b.key()) {
+            let transfer_accounts = Transfer {
+                from: self.taker.to_account_info(),
+                to: self.counter_vault.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+            transfer(cpi_ctx, amount_b)?;
+
+            let sync_accounts = SyncNative {
+                account: self.counter_vault.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), sync_accounts);
+            return sync_native(cpi_ctx);
+        }
+
+        let taker_ata_b = self
+            .taker_ata_b
+            .as_ref()
+            .ok_or(EscrowError::MissingTokenAccount)?;
+
+        let transfer_accounts = TransferChecked {
+            from: taker_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to: self.counter_vault.to_account_info(),
+            authority: self.taker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+
+        // Token-2022 transfer fees are added on top so the counter vault holds the full amount
+        let gross_amount = gross_up_for_fee(&self.mint_b, amount_b)?;
+        transfer_checked(cpi_ctx, gross_amount, self.mint_b.decimals)
+    }
+}
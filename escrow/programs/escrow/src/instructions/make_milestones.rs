@@ -0,0 +1,144 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    constants::MAX_MILESTONES,
+    error::EscrowError,
+    extensions::{check_mint_extensions, gross_up_for_fee},
+    state::{ArbiterTerms, Arbitration, Config, Milestone, MilestoneEscrow, MilestoneTerms},
+};
+
+// ===== MAKE MILESTONES INSTRUCTION ACCOUNTS =====
+// Creates a milestone escrow for a service contract. The payer deposits the sum of
+// every milestone up front; each tranche is then released to the payee one by one.
+// Native SOL is not unwrapped here, so pay in wSOL if SOL is needed.
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeMilestones<'info> {
+    // The payer funds the contract and pays for the escrow account and its vault
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mint::token_program = token_program
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    // Source of the payment
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = payer,
+        associated_token::token_program = token_program
+    )]
+    pub payer_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"milestone", payer.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + MilestoneEscrow::INIT_SPACE
+    )]
+    pub milestone_escrow: Account<'info, MilestoneEscrow>,
+
+    // Holds every unreleased tranche, owned by the milestone escrow PDA
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = milestone_escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    // Global config - no milestone escrow can be opened while the program is paused
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeMilestones<'info> {
+    // - seed: A unique value to derive the milestone escrow PDA
+    // - payee: Wallet that receives each released tranche
+    // - milestones: Amount and description hash of every tranche, in delivery order
+    // - deadline: Unix timestamp after which the payer can refund unreleased tranches alone
+    // - arbitration: Optional arbiter who can split the vault if either side raises a dispute
+    pub fn make_milestones(
+        &mut self,
+        seed: u64,
+        payee: Pubkey,
+        milestones: Vec<MilestoneTerms>,
+        deadline: i64,
+        arbitration: Option<ArbiterTerms>,
+        bumps: &MakeMilestonesBumps,
+    ) -> Result<()> {
+        self.config.check_not_paused()?;
+        require!(
+            !milestones.is_empty() && milestones.len() <= MAX_MILESTONES as usize,
+            EscrowError::InvalidMilestones
+        );
+        require!(
+            milestones.iter().all(|m| m.amount > 0),
+            EscrowError::InvalidMilestones
+        );
+        require!(
+            deadline > Clock::get()?.unix_timestamp,
+            EscrowError::InvalidExpiry
+        );
+        check_mint_extensions(&self.mint)?;
+        let arbitration = arbitration
+            .map(|terms| Arbitration::new(terms, [self.payer.key(), payee]))
+            .transpose()?;
+
+        let total = milestones.iter().try_fold(0u64, |total, m| {
+            total.checked_add(m.amount).ok_or(EscrowError::Overflow)
+        })?;
+
+        self.milestone_escrow.set_inner(MilestoneEscrow {
+            seed,
+            payer: self.payer.key(),
+            payee,
+            mint: self.mint.key(),
+            milestones: milestones
+                .into_iter()
+                .map(|m| Milestone {
+                    amount: m.amount,
+                    description_hash: m.description_hash,
+                    released: false,
+                })
+                .collect(),
+            deadline,
+            payer_approved_refund: false,
+            payee_approved_refund: false,
+            arbitration,
+            bump: bumps.milestone_escrow,
+        });
+
+        self.deposit(total)
+    }
+
+    // Move the whole contract amount into the vault
+    fn deposit(&mut self, total: u64) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: self.payer_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.payer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+
+        // Token-2022 transfer fees are added on top so the vault covers every tranche in full
+        let gross_amount = gross_up_for_fee(&self.mint, total)?;
+        transfer_checked(cpi_ctx, gross_amount, self.mint.decimals)
+    }
+}
@@ -0,0 +1,111 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    error::EscrowError,
+    extensions::{check_mint_extensions, gross_up_for_fee},
+    state::{Config, Vesting, VestingSchedule},
+};
+
+// ===== MAKE VESTING INSTRUCTION ACCOUNTS =====
+// Creates a vesting escrow: the funder deposits the total amount up front and the
+// beneficiary claims it as it vests.
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeVesting<'info> {
+    // The funder deposits the tokens and pays for the vesting account and its vault
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    #[account(
+        mint::token_program = token_program
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = funder,
+        associated_token::token_program = token_program
+    )]
+    pub funder_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = funder,
+        seeds = [b"vesting", funder.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + Vesting::INIT_SPACE
+    )]
+    pub vesting: Account<'info, Vesting>,
+
+    // Holds the unclaimed tokens, owned by the vesting PDA
+    #[account(
+        init,
+        payer = funder,
+        associated_token::mint = mint,
+        associated_token::authority = vesting,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    // Global config - no vesting schedule can be funded while the program is paused
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeVesting<'info> {
+    // - seed: A unique value to derive the vesting PDA
+    // - beneficiary: Wallet that claims the vested tokens
+    // - total: Amount deposited now and fully vested at the end of the schedule
+    // - schedule: Start, cliff and end of the linear schedule
+    // - revoker: Optional wallet that can claw back the unvested part
+    pub fn make_vesting(
+        &mut self,
+        seed: u64,
+        beneficiary: Pubkey,
+        total: u64,
+        schedule: VestingSchedule,
+        revoker: Option<Pubkey>,
+        bumps: &MakeVestingBumps,
+    ) -> Result<()> {
+        self.config.check_not_paused()?;
+        require!(total > 0, EscrowError::InvalidAmount);
+        schedule.validate()?;
+        check_mint_extensions(&self.mint)?;
+
+        self.vesting.set_inner(Vesting {
+            seed,
+            funder: self.funder.key(),
+            beneficiary,
+            mint: self.mint.key(),
+            total,
+            claimed: 0,
+            schedule,
+            revoker,
+            bump: bumps.vesting,
+        });
+
+        let transfer_accounts = TransferChecked {
+            from: self.funder_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.funder.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+
+        // Token-2022 transfer fees are added on top so the vault holds the full total
+        let gross_amount = gross_up_for_fee(&self.mint, total)?;
+        transfer_checked(cpi_ctx, gross_amount, self.mint.decimals)
+    }
+}
@@ -1,7 +1,57 @@
+pub mod initialize_config;
+pub mod update_config;
+pub mod accept_admin;
 pub mod make;
 pub mod take;
+pub mod take_many;
+pub mod take_collection;
 pub mod refund;
+pub mod refund_expired;
+pub mod update_offer;
+pub mod make_counter;
+pub mod accept_counter;
+pub mod close_counter;
+pub mod fill_order;
+pub mod cancel_order;
+pub mod make_bundle;
+pub mod take_bundle;
+pub mod refund_bundle;
+pub mod make_milestones;
+pub mod release_milestone;
+pub mod approve_milestone_refund;
+pub mod refund_milestones;
+pub mod raise_dispute;
+pub mod resolve_dispute;
+pub mod resolve_milestone_dispute;
+pub mod make_vesting;
+pub mod claim_vesting;
+pub mod revoke_vesting;
 
+pub use initialize_config::*;
+pub use update_config::*;
+pub use accept_admin::*;
 pub use make::*;
 pub use take::*;
-pub use refund::*;
\ No newline at end of file
+pub use take_many::*;
+pub use take_collection::*;
+pub use refund::*;
+pub use refund_expired::*;
+pub use update_offer::*;
+pub use make_counter::*;
+pub use accept_counter::*;
+pub use close_counter::*;
+pub use fill_order::*;
+pub use cancel_order::*;
+pub use make_bundle::*;
+pub use take_bundle::*;
+pub use refund_bundle::*;
+pub use make_milestones::*;
+pub use release_milestone::*;
+pub use approve_milestone_refund::*;
+pub use refund_milestones::*;
+pub use raise_dispute::*;
+pub use resolve_dispute::*;
+pub use resolve_milestone_dispute::*;
+pub use make_vesting::*;
+pub use claim_vesting::*;
+pub use revoke_vesting::*;
\ No newline at end of file
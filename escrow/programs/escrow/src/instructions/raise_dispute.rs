@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::EscrowError,
+    state::{Escrow, MilestoneEscrow},
+};
+
+// ===== RAISE DISPUTE INSTRUCTION ACCOUNTS =====
+// The maker or the named taker of an arbitrated escrow raises a dispute, once the taker has
+// paid something into it. Take, refund, update and counter-offers are frozen until the
+// arbiter resolves it, or until DISPUTE_TIMEOUT passes and the maker can refund.
+#[derive(Accounts)]
+pub struct RaiseDispute<'info> {
+    // The maker or the named taker
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+impl<'info> RaiseDispute<'info> {
+    pub fn raise_dispute(&mut self) -> Result<()> {
+        let signer = self.signer.key();
+        require!(
+            signer == self.escrow.maker || Some(signer) == self.escrow.taker,
+            EscrowError::Unauthorized
+        );
+        // A taker who hasn't paid anything has nothing at stake to dispute
+        require!(self.escrow.filled > 0, EscrowError::NothingToDispute);
+
+        self.escrow
+            .arbitration
+            .as_mut()
+            .ok_or(EscrowError::NoArbiter)?
+            .raise(Clock::get()?.unix_timestamp)
+    }
+}
+
+// ===== RAISE MILESTONE DISPUTE INSTRUCTION ACCOUNTS =====
+// The payer or the payee of an arbitrated milestone escrow raises a dispute. Releases
+// and refunds are frozen until the arbiter resolves it, or until DISPUTE_TIMEOUT passes.
+#[derive(Accounts)]
+pub struct RaiseMilestoneDispute<'info> {
+    // The payer or the payee
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"milestone",
+            milestone_escrow.payer.as_ref(),
+            milestone_escrow.seed.to_le_bytes().as_ref()
+        ],
+        bump = milestone_escrow.bump,
+    )]
+    pub milestone_escrow: Account<'info, MilestoneEscrow>,
+}
+
+impl<'info> RaiseMilestoneDispute<'info> {
+    pub fn raise_dispute(&mut self) -> Result<()> {
+        let signer = self.signer.key();
+        require!(
+            signer == self.milestone_escrow.payer || signer == self.milestone_escrow.payee,
+            EscrowError::Unauthorized
+        );
+
+        self.milestone_escrow
+            .arbitration
+            .as_mut()
+            .ok_or(EscrowError::NoArbiter)?
+            .raise(Clock::get()?.unix_timestamp)
+    }
+}
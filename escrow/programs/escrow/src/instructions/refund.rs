@@ -1,33 +1,34 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token_interface::{TokenAccount, TokenInterface, Mint, TransferChecked, transfer_checked, CloseAccount, close_account}};
+use anchor_spl::{associated_token::AssociatedToken, token::spl_token::native_mint, token_interface::{TokenAccount, TokenInterface, Mint, TransferChecked, transfer_checked, CloseAccount, close_account}};
 
-use crate::state::Escrow;
+use crate::{error::EscrowError, events::OfferRefunded, extensions::harvest_withheld_fees, state::{Escrow, OfferBook}};
 
 
 #[derive(Accounts)]
-#[instruction(seed: u64)]
 pub struct Refund<'info> {
     #[account(mut)]
     pub maker: Signer<'info>,
     #[account(
+        mut,
         mint::token_program = token_program
     )]
     pub mint_a: InterfaceAccount<'info, Mint>,
 
+    // Left out for native SOL, which is unwrapped by closing the vault to the maker
     #[account(
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = maker,
         associated_token::token_program = token_program
     )]
-    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
         close = maker,
         has_one = mint_a,
         has_one = maker,
-        seeds = [b"escrow", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
         bump = escrow.bump,
     )]
     pub escrow: Account<'info, Escrow>,
@@ -40,6 +41,19 @@ pub struct Refund<'info> {
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
 
+    // The offer book page listing this escrow - the entry is removed as the offer is closed
+    #[account(
+        mut,
+        seeds = [
+            b"book",
+            escrow.mint_a.as_ref(),
+            escrow.mint_b.as_ref(),
+            escrow.book_page.to_le_bytes().as_ref()
+        ],
+        bump = book.bump,
+    )]
+    pub book: Account<'info, OfferBook>,
+
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
@@ -47,6 +61,13 @@ pub struct Refund<'info> {
 
 impl <'info> Refund<'info> {
     pub fn refund_and_close_vault(&mut self) -> Result<()> {
+        self.escrow.check_refundable(Clock::get()?.unix_timestamp)?;
+        self.book.remove(&self.escrow.key())?;
+        emit!(OfferRefunded {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            amount_a: self.vault.amount,
+        });
 
         let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
@@ -55,16 +76,23 @@ impl <'info> Refund<'info> {
             &[self.escrow.bump]
         ]];
 
-        let transfer_accounts = TransferChecked {
-            from: self.vault.to_account_info(),
-            mint: self.mint_a.to_account_info(),
-            to: self.maker_ata_a.to_account_info(),
-            authority: self.escrow.to_account_info()
-    
-        };
+        // Closing a wSOL vault already hands its whole balance to the maker as SOL
+        if !native_mint::check_id(&self.mint_a.key()) {
+            let maker_ata_a = self.maker_ata_a.as_ref().ok_or(EscrowError::MissingTokenAccount)?;
+
+            let transfer_accounts = TransferChecked {
+                from: self.vault.to_account_info(),
+                mint: self.mint_a.to_account_info(),
+                to: maker_ata_a.to_account_info(),
+                authority: self.escrow.to_account_info()
+        
+            };
+
+            let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), transfer_accounts, &signer_seeds);
+            transfer_checked(cpi_ctx, self.vault.amount, self.mint_a.decimals)?;
+        }
 
-        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), transfer_accounts, &signer_seeds);
-        transfer_checked(cpi_ctx, self.vault.amount, self.mint_a.decimals)?;
+        harvest_withheld_fees(self.token_program.to_account_info(), self.mint_a.to_account_info(), self.vault.to_account_info())?;
 
         let close_accounts = CloseAccount {
             account: self.vault.to_account_info(),
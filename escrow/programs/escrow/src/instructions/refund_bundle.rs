@@ -0,0 +1,128 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{
+        create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create,
+    },
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{error::EscrowError, extensions::harvest_withheld_fees, state::Bundle};
+
+// ===== REFUND BUNDLE INSTRUCTION ACCOUNTS =====
+// Lets the maker cancel a bundle offer, returning every offered leg and closing the vaults.
+//
+// remaining_accounts holds one triple per offered leg, in the same order as `offered`:
+//   [mint, vault, maker_ata]
+// Mints with a Token-2022 transfer fee must be writable so the fees withheld in their
+// vaults can be harvested before the vaults are closed.
+#[derive(Accounts)]
+pub struct RefundBundle<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [b"bundle", maker.key().as_ref(), bundle.seed.to_le_bytes().as_ref()],
+        bump = bundle.bump,
+    )]
+    pub bundle: Account<'info, Bundle>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RefundBundle<'info> {
+    pub fn refund_and_close_vaults(
+        &mut self,
+        remaining: &'info [AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(
+            remaining.len() == self.bundle.offered.len() * 3,
+            EscrowError::InvalidBundleAccounts
+        );
+
+        let maker_key = self.maker.key();
+        let bundle_seed = self.bundle.seed.to_le_bytes();
+        let seeds = &[
+            b"bundle",
+            maker_key.as_ref(),
+            &bundle_seed[..],
+            &[self.bundle.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        for (leg, accounts) in self.bundle.offered.iter().zip(remaining.chunks(3)) {
+            let [mint_info, vault, maker_ata] = accounts else {
+                return err!(EscrowError::InvalidBundleAccounts);
+            };
+            require_keys_eq!(
+                mint_info.key(),
+                leg.mint,
+                EscrowError::InvalidBundleAccounts
+            );
+            require_keys_eq!(
+                vault.key(),
+                get_associated_token_address_with_program_id(
+                    &self.bundle.key(),
+                    &leg.mint,
+                    &self.token_program.key()
+                ),
+                EscrowError::InvalidBundleAccounts
+            );
+            let mint = InterfaceAccount::<Mint>::try_from(mint_info)?;
+
+            // The maker may have closed their token account since making the bundle
+            let create_accounts = Create {
+                payer: self.maker.to_account_info(),
+                associated_token: maker_ata.clone(),
+                authority: self.maker.to_account_info(),
+                mint: mint_info.clone(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            };
+            create_idempotent(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                create_accounts,
+            ))?;
+
+            let amount = InterfaceAccount::<TokenAccount>::try_from(vault)?.amount;
+            let transfer_accounts = TransferChecked {
+                from: vault.clone(),
+                mint: mint_info.clone(),
+                to: maker_ata.clone(),
+                authority: self.bundle.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                transfer_accounts,
+                signer_seeds,
+            );
+            transfer_checked(cpi_ctx, amount, mint.decimals)?;
+
+            harvest_withheld_fees(
+                self.token_program.to_account_info(),
+                mint_info.clone(),
+                vault.clone(),
+            )?;
+            let close_accounts = CloseAccount {
+                account: vault.clone(),
+                destination: self.maker.to_account_info(),
+                authority: self.bundle.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                close_accounts,
+                signer_seeds,
+            );
+            close_account(cpi_ctx)?;
+        }
+
+        Ok(())
+    }
+}
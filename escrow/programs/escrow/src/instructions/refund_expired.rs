@@ -0,0 +1,146 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::spl_token::native_mint,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    error::EscrowError,
+    events::OfferRefunded,
+    extensions::harvest_withheld_fees,
+    state::{Escrow, OfferBook},
+};
+
+// ===== REFUND EXPIRED INSTRUCTION ACCOUNTS =====
+// Permissionless crank that cleans up an expired offer.
+// Anyone can call it; the tokens and all rent always go back to the maker.
+#[derive(Accounts)]
+pub struct RefundExpired<'info> {
+    // Whoever cranks the refund - pays the fee and, if needed, the rent for maker_ata_a
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    // The original creator of the escrow (not a signer in this transaction)
+    // Receives the remaining token A and the rent of the vault and escrow
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        mint::token_program = token_program
+    )]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    // The maker may have closed their token A account since making the offer
+    // Left out for native SOL, which is unwrapped by closing the vault to the maker
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = mint_a,
+        has_one = maker,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    // The offer book page listing this escrow - the entry is removed as the offer is closed
+    #[account(
+        mut,
+        seeds = [
+            b"book",
+            escrow.mint_a.as_ref(),
+            escrow.mint_b.as_ref(),
+            escrow.book_page.to_le_bytes().as_ref()
+        ],
+        bump = book.bump,
+    )]
+    pub book: Account<'info, OfferBook>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RefundExpired<'info> {
+    pub fn refund_expired(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.escrow.check_refundable(now)?;
+        // Until expiry only the maker can cancel the offer
+        require!(now >= self.escrow.expires_at, EscrowError::OfferNotExpired);
+        self.book.remove(&self.escrow.key())?;
+        emit!(OfferRefunded {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            amount_a: self.vault.amount,
+        });
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        // Closing a wSOL vault already hands its whole balance to the maker as SOL
+        if !native_mint::check_id(&self.mint_a.key()) {
+            let maker_ata_a = self
+                .maker_ata_a
+                .as_ref()
+                .ok_or(EscrowError::MissingTokenAccount)?;
+
+            let transfer_accounts = TransferChecked {
+                from: self.vault.to_account_info(),
+                mint: self.mint_a.to_account_info(),
+                to: maker_ata_a.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                transfer_accounts,
+                &signer_seeds,
+            );
+            transfer_checked(cpi_ctx, self.vault.amount, self.mint_a.decimals)?;
+        }
+
+        harvest_withheld_fees(
+            self.token_program.to_account_info(),
+            self.mint_a.to_account_info(),
+            self.vault.to_account_info(),
+        )?;
+
+        let close_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.maker.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+        let close_cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            close_accounts,
+            &signer_seeds,
+        );
+
+        close_account(close_cpi_ctx)
+    }
+}
@@ -0,0 +1,112 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{error::EscrowError, extensions::harvest_withheld_fees, state::MilestoneEscrow};
+
+// ===== REFUND MILESTONES INSTRUCTION ACCOUNTS =====
+// Returns every unreleased tranche to the payer and closes the vault and the escrow.
+// Allowed once both parties approved the refund, or by the payer alone after the deadline.
+#[derive(Accounts)]
+pub struct RefundMilestones<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        mint::token_program = token_program
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    // Where the payer gets the unreleased tranches back
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = payer,
+        associated_token::token_program = token_program
+    )]
+    pub payer_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        has_one = mint,
+        seeds = [
+            b"milestone",
+            payer.key().as_ref(),
+            milestone_escrow.seed.to_le_bytes().as_ref()
+        ],
+        bump = milestone_escrow.bump,
+    )]
+    pub milestone_escrow: Account<'info, MilestoneEscrow>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = milestone_escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RefundMilestones<'info> {
+    pub fn refund_milestones(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.milestone_escrow.check_refundable(now)?;
+        require!(
+            self.milestone_escrow.refund_approved() || now >= self.milestone_escrow.deadline,
+            EscrowError::RefundNotAllowed
+        );
+
+        let payer_key = self.payer.key();
+        let escrow_seed = self.milestone_escrow.seed.to_le_bytes();
+        let seeds = &[
+            b"milestone",
+            payer_key.as_ref(),
+            &escrow_seed[..],
+            &[self.milestone_escrow.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let transfer_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.payer_ata.to_account_info(),
+            authority: self.milestone_escrow.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            transfer_accounts,
+            signer_seeds,
+        );
+        transfer_checked(cpi_ctx, self.vault.amount, self.mint.decimals)?;
+
+        harvest_withheld_fees(
+            self.token_program.to_account_info(),
+            self.mint.to_account_info(),
+            self.vault.to_account_info(),
+        )?;
+        let close_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.payer.to_account_info(),
+            authority: self.milestone_escrow.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            close_accounts,
+            signer_seeds,
+        );
+        close_account(cpi_ctx)
+    }
+}
@@ -0,0 +1,134 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{error::EscrowError, extensions::harvest_withheld_fees, state::MilestoneEscrow};
+
+// ===== RELEASE MILESTONE INSTRUCTION ACCOUNTS =====
+// The payer approves a delivered milestone, releasing its tranche to the payee.
+// Releasing the last open milestone closes the vault and the escrow to the payer.
+#[derive(Accounts)]
+pub struct ReleaseMilestone<'info> {
+    // The payer approving the milestone - pays for payee_ata if it has to be created
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub payee: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        mint::token_program = token_program
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    // Where the payee receives the tranche
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = payee,
+        associated_token::token_program = token_program
+    )]
+    pub payee_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        has_one = payer,
+        has_one = payee,
+        has_one = mint,
+        seeds = [
+            b"milestone",
+            payer.key().as_ref(),
+            milestone_escrow.seed.to_le_bytes().as_ref()
+        ],
+        bump = milestone_escrow.bump,
+    )]
+    pub milestone_escrow: Account<'info, MilestoneEscrow>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = milestone_escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ReleaseMilestone<'info> {
+    // - index: Position of the milestone in the escrow's list
+    pub fn release_milestone(&mut self, index: u8) -> Result<()> {
+        self.milestone_escrow.check_not_disputed()?;
+
+        let milestone = self
+            .milestone_escrow
+            .milestones
+            .get_mut(index as usize)
+            .ok_or(EscrowError::InvalidMilestone)?;
+        require!(!milestone.released, EscrowError::InvalidMilestone);
+        milestone.released = true;
+        let mut amount = milestone.amount;
+
+        // The last release empties the vault, so tokens anyone sent to it can't block the close
+        let done = self.milestone_escrow.milestones.iter().all(|m| m.released);
+        if done {
+            amount = self.vault.amount;
+        }
+
+        let payer_key = self.payer.key();
+        let escrow_seed = self.milestone_escrow.seed.to_le_bytes();
+        let seeds = &[
+            b"milestone",
+            payer_key.as_ref(),
+            &escrow_seed[..],
+            &[self.milestone_escrow.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        // Any Token-2022 transfer fee on the way out is withheld from what the payee receives
+        let transfer_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.payee_ata.to_account_info(),
+            authority: self.milestone_escrow.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            transfer_accounts,
+            signer_seeds,
+        );
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+
+        if !done {
+            return Ok(());
+        }
+
+        // Every tranche is paid out, so the contract is done
+        harvest_withheld_fees(
+            self.token_program.to_account_info(),
+            self.mint.to_account_info(),
+            self.vault.to_account_info(),
+        )?;
+        let close_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.payer.to_account_info(),
+            authority: self.milestone_escrow.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            close_accounts,
+            signer_seeds,
+        );
+        close_account(cpi_ctx)?;
+
+        self.milestone_escrow.close(self.payer.to_account_info())
+    }
+}
@@ -0,0 +1,186 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    error::EscrowError,
+    events::OfferRefunded,
+    extensions::harvest_withheld_fees,
+    state::{Escrow, OfferBook},
+};
+
+// ===== RESOLVE DISPUTE INSTRUCTION ACCOUNTS =====
+// The arbiter of a disputed escrow splits the token A in the vault between the maker
+// and the named taker, after taking the arbitration fee. The vault and the escrow are
+// closed to the maker. Native SOL is paid out as wSOL, since none of the recipients sign.
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    // The arbiter named at make time - pays for any token accounts created on demand
+    #[account(mut)]
+    pub arbiter: Signer<'info>,
+
+    // The maker - gets their share and all rent back
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        constraint = escrow.taker == Some(taker.key()) @ EscrowError::Unauthorized
+    )]
+    pub taker: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        mint::token_program = token_program
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = arbiter,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = arbiter,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Where the arbitration fee is paid
+    #[account(
+        init_if_needed,
+        payer = arbiter,
+        associated_token::mint = mint_a,
+        associated_token::authority = arbiter,
+        associated_token::token_program = token_program
+    )]
+    pub arbiter_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        has_one = mint_a,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Box<Account<'info, Escrow>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // The offer book page listing this escrow - the entry is removed as the offer is closed
+    #[account(
+        mut,
+        seeds = [
+            b"book",
+            escrow.mint_a.as_ref(),
+            escrow.mint_b.as_ref(),
+            escrow.book_page.to_le_bytes().as_ref()
+        ],
+        bump = book.bump,
+    )]
+    pub book: Box<Account<'info, OfferBook>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ResolveDispute<'info> {
+    // - maker_share_bps: Share of the vault, after the arbitration fee, that goes to the maker
+    pub fn resolve_dispute(&mut self, maker_share_bps: u16) -> Result<()> {
+        let arbitration = self
+            .escrow
+            .arbitration
+            .as_ref()
+            .ok_or(EscrowError::NoArbiter)?;
+        require_keys_eq!(
+            arbitration.arbiter,
+            self.arbiter.key(),
+            EscrowError::Unauthorized
+        );
+        require!(arbitration.disputed, EscrowError::NoDispute);
+        self.book.remove(&self.escrow.key())?;
+
+        let (fee, to_maker, to_taker) = arbitration.split(self.vault.amount, maker_share_bps)?;
+        // The offer ends here, with the maker's share of token A returned
+        emit!(OfferRefunded {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            amount_a: to_maker,
+        });
+
+        let maker_key = self.maker.key();
+        let escrow_seed = self.escrow.seed.to_le_bytes();
+        let seeds = &[
+            b"escrow",
+            maker_key.as_ref(),
+            &escrow_seed[..],
+            &[self.escrow.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        self.pay_out(&self.arbiter_ata_a, fee, signer_seeds)?;
+        self.pay_out(&self.maker_ata_a, to_maker, signer_seeds)?;
+        self.pay_out(&self.taker_ata_a, to_taker, signer_seeds)?;
+
+        harvest_withheld_fees(
+            self.token_program.to_account_info(),
+            self.mint_a.to_account_info(),
+            self.vault.to_account_info(),
+        )?;
+        let close_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.maker.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            close_accounts,
+            signer_seeds,
+        );
+        close_account(cpi_ctx)
+    }
+
+    // Move part of the vault to one of the parties
+    fn pay_out(
+        &self,
+        to: &InterfaceAccount<'info, TokenAccount>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let transfer_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            to: to.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            transfer_accounts,
+            signer_seeds,
+        );
+        transfer_checked(cpi_ctx, amount, self.mint_a.decimals)
+    }
+}
@@ -0,0 +1,163 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{error::EscrowError, extensions::harvest_withheld_fees, state::MilestoneEscrow};
+
+// ===== RESOLVE MILESTONE DISPUTE INSTRUCTION ACCOUNTS =====
+// The arbiter of a disputed milestone escrow splits every unreleased tranche between
+// the payer and the payee, after taking the arbitration fee. The vault and the escrow
+// are closed to the payer.
+#[derive(Accounts)]
+pub struct ResolveMilestoneDispute<'info> {
+    // The arbiter named at make time - pays for any token accounts created on demand
+    #[account(mut)]
+    pub arbiter: Signer<'info>,
+
+    // The payer - gets their share and all rent back
+    #[account(mut)]
+    pub payer: SystemAccount<'info>,
+
+    pub payee: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        mint::token_program = token_program
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = arbiter,
+        associated_token::mint = mint,
+        associated_token::authority = payer,
+        associated_token::token_program = token_program
+    )]
+    pub payer_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = arbiter,
+        associated_token::mint = mint,
+        associated_token::authority = payee,
+        associated_token::token_program = token_program
+    )]
+    pub payee_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Where the arbitration fee is paid
+    #[account(
+        init_if_needed,
+        payer = arbiter,
+        associated_token::mint = mint,
+        associated_token::authority = arbiter,
+        associated_token::token_program = token_program
+    )]
+    pub arbiter_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        has_one = payee,
+        has_one = mint,
+        seeds = [
+            b"milestone",
+            payer.key().as_ref(),
+            milestone_escrow.seed.to_le_bytes().as_ref()
+        ],
+        bump = milestone_escrow.bump,
+    )]
+    pub milestone_escrow: Box<Account<'info, MilestoneEscrow>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = milestone_escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ResolveMilestoneDispute<'info> {
+    // - payer_share_bps: Share of the vault, after the arbitration fee, that goes to the payer
+    pub fn resolve_dispute(&mut self, payer_share_bps: u16) -> Result<()> {
+        let arbitration = self
+            .milestone_escrow
+            .arbitration
+            .as_ref()
+            .ok_or(EscrowError::NoArbiter)?;
+        require_keys_eq!(
+            arbitration.arbiter,
+            self.arbiter.key(),
+            EscrowError::Unauthorized
+        );
+        require!(arbitration.disputed, EscrowError::NoDispute);
+
+        let (fee, to_payer, to_payee) = arbitration.split(self.vault.amount, payer_share_bps)?;
+
+        let payer_key = self.payer.key();
+        let escrow_seed = self.milestone_escrow.seed.to_le_bytes();
+        let seeds = &[
+            b"milestone",
+            payer_key.as_ref(),
+            &escrow_seed[..],
+            &[self.milestone_escrow.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        self.pay_out(&self.arbiter_ata, fee, signer_seeds)?;
+        self.pay_out(&self.payer_ata, to_payer, signer_seeds)?;
+        self.pay_out(&self.payee_ata, to_payee, signer_seeds)?;
+
+        harvest_withheld_fees(
+            self.token_program.to_account_info(),
+            self.mint.to_account_info(),
+            self.vault.to_account_info(),
+        )?;
+        let close_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.payer.to_account_info(),
+            authority: self.milestone_escrow.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            close_accounts,
+            signer_seeds,
+        );
+        close_account(cpi_ctx)
+    }
+
+    // Move part of the vault to one of the parties
+    fn pay_out(
+        &self,
+        to: &InterfaceAccount<'info, TokenAccount>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let transfer_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: to.to_account_info(),
+            authority: self.milestone_escrow.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            transfer_accounts,
+            signer_seeds,
+        );
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)
+    }
+}
@@ -0,0 +1,138 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{error::EscrowError, extensions::harvest_withheld_fees, state::Vesting};
+
+// ===== REVOKE VESTING INSTRUCTION ACCOUNTS =====
+// The revoker stops a vesting schedule. Whatever has vested but is unclaimed still goes
+// to the beneficiary, the unvested part goes back to the funder, and the vault and the
+// vesting account are closed to the funder.
+#[derive(Accounts)]
+pub struct RevokeVesting<'info> {
+    // The revoker named at creation - pays for any token accounts created on demand
+    #[account(mut)]
+    pub revoker: Signer<'info>,
+
+    #[account(mut)]
+    pub funder: SystemAccount<'info>,
+
+    pub beneficiary: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        mint::token_program = token_program
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = revoker,
+        associated_token::mint = mint,
+        associated_token::authority = funder,
+        associated_token::token_program = token_program
+    )]
+    pub funder_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = revoker,
+        associated_token::mint = mint,
+        associated_token::authority = beneficiary,
+        associated_token::token_program = token_program
+    )]
+    pub beneficiary_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        close = funder,
+        has_one = funder,
+        has_one = beneficiary,
+        has_one = mint,
+        constraint = vesting.revoker == Some(revoker.key()) @ EscrowError::Unauthorized,
+        seeds = [b"vesting", funder.key().as_ref(), vesting.seed.to_le_bytes().as_ref()],
+        bump = vesting.bump,
+    )]
+    pub vesting: Box<Account<'info, Vesting>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vesting,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RevokeVesting<'info> {
+    pub fn revoke(&mut self) -> Result<()> {
+        let vested = self.vesting.vested(Clock::get()?.unix_timestamp)?;
+        let to_beneficiary = vested - self.vesting.claimed;
+        // The funder gets the unvested part plus anything else sent to the vault, which
+        // has to be empty before it can be closed
+        let to_funder = self.vault.amount - to_beneficiary;
+
+        let funder_key = self.funder.key();
+        let vesting_seed = self.vesting.seed.to_le_bytes();
+        let seeds = &[
+            b"vesting",
+            funder_key.as_ref(),
+            &vesting_seed[..],
+            &[self.vesting.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        self.pay_out(&self.beneficiary_ata, to_beneficiary, signer_seeds)?;
+        self.pay_out(&self.funder_ata, to_funder, signer_seeds)?;
+
+        harvest_withheld_fees(
+            self.token_program.to_account_info(),
+            self.mint.to_account_info(),
+            self.vault.to_account_info(),
+        )?;
+        let close_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.funder.to_account_info(),
+            authority: self.vesting.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            close_accounts,
+            signer_seeds,
+        );
+        close_account(cpi_ctx)
+    }
+
+    fn pay_out(
+        &self,
+        to: &InterfaceAccount<'info, TokenAccount>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let transfer_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: to.to_account_info(),
+            authority: self.vesting.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            transfer_accounts,
+            signer_seeds,
+        );
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)
+    }
+}
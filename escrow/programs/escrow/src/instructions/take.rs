@@ -1,13 +1,22 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
 use anchor_spl::{
     associated_token::AssociatedToken,
+    token::spl_token::native_mint,
     token_interface::{
         close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
         TransferChecked,
     },
 };
 
-use crate::state::Escrow;
+use crate::{
+    error::EscrowError,
+    events::OfferTaken,
+    extensions::{gross_up_for_fee, harvest_withheld_fees},
+    state::{Config, Escrow, OfferBook},
+};
 
 // ===== TAKE INSTRUCTION ACCOUNTS =====
 // This file implements the "take" side of the escrow, where the taker accepts
@@ -22,14 +31,36 @@ pub struct Take<'info> {
 
     // The original creator of the escrow (not a signer in this transaction)
     // Verify this matches the maker pubkey stored in the escrow state
+    // Marked mut because it receives the rent of the vault and escrow once the offer is fully filled
     /// CHECK: Safe because we validate this account's address matches the maker stored in escrow state
     #[account(
+        mut,
         constraint = maker.key() == escrow.maker
     )]
     pub maker: AccountInfo<'info>,
 
+    // maker_recipient: UncheckedAccount - Where the maker routes token B, must match the escrow
+    // This is the maker unless they picked another address at make time, which may be
+    // program-owned such as a multisig vault PDA
+    /// CHECK: Safe because the address must match the escrow and only its token B account
+    /// is written to, or lamports are sent to it when token B is native SOL
+    #[account(
+        mut,
+        address = escrow.maker_recipient
+    )]
+    pub maker_recipient: UncheckedAccount<'info>,
+
+    // recipient: UncheckedAccount - Optional address that receives token A instead of the taker,
+    // e.g. a cold wallet when a bot signs the take
+    /// CHECK: Safe because it is only the authority of recipient_ata_a, or is sent lamports
+    /// when token A is native SOL
+    #[account(mut)]
+    pub recipient: Option<UncheckedAccount<'info>>,
+
     // mint_a: InterfaceAccount<Mint> - The token being offered by maker
+    // Writable so Token-2022 fees withheld in the vault can be harvested before it is closed
     #[account(
+        mut,
         mint::token_program = token_program
     )]
     pub mint_a: InterfaceAccount<'info, Mint>,
@@ -41,40 +72,69 @@ pub struct Take<'info> {
     pub mint_b: InterfaceAccount<'info, Mint>,
 
     // taker_ata_a: InterfaceAccount<TokenAccount> - Where taker receives token A
+    // Created on demand; left out for native SOL and when an SPL token A is routed to a recipient
     #[account(
-        mut,
+        init_if_needed,
+        payer = taker,
         associated_token::mint = mint_a,
         associated_token::authority = taker,
         associated_token::token_program = token_program
     )]
-    pub taker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    pub taker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // recipient_ata_a: InterfaceAccount<TokenAccount> - Where the recipient receives token A
+    // Created on demand; not used for native SOL, which is unwrapped and sent to the recipient's wallet
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program
+    )]
+    pub recipient_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // unwrap_a: InterfaceAccount<TokenAccount> - Temporary wSOL account owned by the escrow
+    // Only for native SOL: token A passes through it and it is closed within the same
+    // instruction to unwrap, so the taker's own wSOL account is never touched
+    #[account(
+        init,
+        payer = taker,
+        seeds = [b"unwrap", escrow.key().as_ref()],
+        bump,
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program
+    )]
+    pub unwrap_a: Option<InterfaceAccount<'info, TokenAccount>>,
 
     // taker_ata_b: InterfaceAccount<TokenAccount> - Source of taker's token B
+    // Left out when token B is native SOL, which is paid straight from the taker's wallet
     #[account(
         mut,
         associated_token::mint = mint_b,
         associated_token::authority = taker,
         associated_token::token_program = token_program
     )]
-    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    pub taker_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
 
-    // maker_ata_b: InterfaceAccount<TokenAccount> - Where maker receives token B
+    // maker_ata_b: InterfaceAccount<TokenAccount> - Where the maker's recipient receives token B
+    // Created on demand; left out when token B is native SOL, which is paid straight to the wallet
     #[account(
-        mut,
+        init_if_needed,
+        payer = taker,
         associated_token::mint = mint_b,
-        associated_token::authority = maker,
+        associated_token::authority = maker_recipient,
         associated_token::token_program = token_program
     )]
-    pub maker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    pub maker_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
 
     // escrow: Account<Escrow> - The escrow state account (verify using seeds)
     // escrow should use seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()]
-    // Mark for closure to the maker after the trade completes
+    // Closed to the maker by hand once the last fill empties the offer, since partial fills keep it open
     #[account(
         mut,
         seeds = [b"escrow", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
         bump = escrow.bump,
-        close = maker
     )]
     pub escrow: Account<'info, Escrow>,
 
@@ -86,6 +146,45 @@ pub struct Take<'info> {
         associated_token::token_program = token_program
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    // book: Account<OfferBook> - The offer book page listing this escrow, unlisted once fully filled
+    #[account(
+        mut,
+        seeds = [
+            b"book",
+            escrow.mint_a.as_ref(),
+            escrow.mint_b.as_ref(),
+            escrow.book_page.to_le_bytes().as_ref()
+        ],
+        bump = book.bump,
+    )]
+    pub book: Account<'info, OfferBook>,
+
+    // config: Account<Config> - Global config holding the protocol fee and the pause switch
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    // fee_recipient: SystemAccount - Receives the protocol fee, must match the config
+    #[account(
+        mut,
+        address = config.fee_recipient
+    )]
+    pub fee_recipient: SystemAccount<'info>,
+
+    // fee_ata_b: InterfaceAccount<TokenAccount> - Where the protocol fee in token B is paid
+    // Created on demand; left out when token B is native SOL
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = fee_recipient,
+        associated_token::token_program = token_program
+    )]
+    pub fee_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
+
     // Required programs
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
@@ -94,38 +193,167 @@ pub struct Take<'info> {
 
 impl<'info> Take<'info> {
     // Main function that orchestrates the entire trade execution
-    pub fn execute_trade(&self) -> Result<()> {
-        // 1. Transfer token B from taker to maker
-        self.transfer_b_to_maker()?;
+    // Fills everything that is still open on the offer, as long as that costs at most max_amount_b
+    pub fn execute_trade(
+        &mut self,
+        max_amount_b: u64,
+        min_amount_a: u64,
+        proof: &[[u8; 32]],
+    ) -> Result<()> {
+        require!(
+            self.cost_of(self.escrow.remaining)? <= max_amount_b,
+            EscrowError::SlippageExceeded
+        );
+        self.fill(self.escrow.remaining, min_amount_a, proof)
+    }
+
+    // Fills only part of the offer, paying amount_b of token B for a pro-rata share of the vault
+    pub fn execute_partial_trade(
+        &mut self,
+        amount_b: u64,
+        min_amount_a: u64,
+        proof: &[[u8; 32]],
+    ) -> Result<()> {
+        self.fill(amount_b, min_amount_a, proof)
+    }
+
+    // - min_amount_a: Least token A the taker accepts, in case the maker withdrew from the vault
+    fn fill(&mut self, amount_b: u64, min_amount_a: u64, proof: &[[u8; 32]]) -> Result<()> {
+        self.config.check_not_paused()?;
+        self.escrow.check_not_disputed()?;
+        self.escrow.check_not_collection()?;
+        self.escrow.check_taker(&self.taker.key(), proof)?;
+        require!(
+            Clock::get()?.unix_timestamp < self.escrow.expires_at,
+            EscrowError::OfferExpired
+        );
+        require!(amount_b > 0, EscrowError::InvalidAmount);
+        require!(amount_b <= self.escrow.remaining, EscrowError::FillExceedsRemaining);
+
+        // 1. Work out how much token A this fill buys at the offer's fixed rate
+        let amount_a = self.escrow.amount_a_for(self.vault.amount, amount_b)?;
+        require!(amount_a > 0, EscrowError::FillTooSmall);
+        require!(amount_a >= min_amount_a, EscrowError::SlippageExceeded);
+
+        // 2. Transfer token B from taker to maker, minus the protocol fee
+        let fee = self.config.fee_for(amount_b)?;
+        self.transfer_b_to_maker(amount_b - fee)?;
+        if fee > 0 {
+            self.transfer_fee(fee)?;
+        }
 
-        // 2. Transfer token A from vault to taker
-        self.transfer_a_to_taker()?;
+        // 3. Transfer token A from vault to taker
+        self.transfer_a_to_taker(amount_a)?;
 
-        // 3. Close the vault token account
-        self.close_vault()?;
+        // 4. Record the fill on the escrow
+        self.escrow.remaining -= amount_b;
+        self.escrow.filled = self
+            .escrow
+            .filled
+            .checked_add(amount_b)
+            .ok_or(EscrowError::Overflow)?;
+        emit!(OfferTaken {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            amount_a,
+            amount_b,
+            remaining: self.escrow.remaining,
+        });
+
+        // 5. Once fully filled, close the vault token account and the escrow itself
+        if self.escrow.remaining == 0 {
+            self.book.remove(&self.escrow.key())?;
+            self.close_vault()?;
+            self.escrow.close(self.maker.to_account_info())?;
+        }
 
         Ok(())
     }
-    // Helper function to transfer token B from taker to maker
-    fn transfer_b_to_maker(&self) -> Result<()> {
+
+    // Token B leaving the taker for a fill of `amount_b`: the maker's share and the
+    // protocol fee, each grossed up for any Token-2022 transfer fee as transfer_b sends them
+    fn cost_of(&self, amount_b: u64) -> Result<u64> {
+        let fee = self.config.fee_for(amount_b)?;
+        let mut cost = gross_up_for_fee(&self.mint_b, amount_b - fee)?;
+        if fee > 0 {
+            cost = cost
+                .checked_add(gross_up_for_fee(&self.mint_b, fee)?)
+                .ok_or(EscrowError::Overflow)?;
+        }
+        Ok(cost)
+    }
+
+    // Helper function to transfer token B from taker to the maker's recipient
+    fn transfer_b_to_maker(&self, amount: u64) -> Result<()> {
+        self.transfer_b(
+            self.maker_recipient.to_account_info(),
+            self.maker_ata_b.as_ref(),
+            amount,
+        )
+    }
+
+    // Helper function to pay the protocol fee in token B
+    fn transfer_fee(&self, amount: u64) -> Result<()> {
+        self.transfer_b(
+            self.fee_recipient.to_account_info(),
+            self.fee_ata_b.as_ref(),
+            amount,
+        )
+    }
+
+    // Transfer token B from the taker to a wallet, or to its token account for SPL mints
+    fn transfer_b(
+        &self,
+        wallet: AccountInfo<'info>,
+        ata: Option<&InterfaceAccount<'info, TokenAccount>>,
+        amount: u64,
+    ) -> Result<()> {
+        // Native SOL goes straight from wallet to wallet, no wSOL involved
+        if native_mint::check_id(&self.mint_b.key()) {
+            let transfer_accounts = Transfer {
+                from: self.taker.to_account_info(),
+                to: wallet,
+            };
+            let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+            return transfer(cpi_ctx, amount);
+        }
+
+        let (Some(taker_ata_b), Some(ata)) = (&self.taker_ata_b, ata) else {
+            return err!(EscrowError::MissingTokenAccount);
+        };
+
         // Create CPI to token program for transferring token B
         let cpi_program = self.token_program.to_account_info();
 
         // Configure the transfer accounts for token B
         let transfer_accounts = TransferChecked {
-            from: self.taker_ata_b.to_account_info(),
+            from: taker_ata_b.to_account_info(),
             mint: self.mint_b.to_account_info(),
-            to: self.maker_ata_b.to_account_info(),
+            to: ata.to_account_info(),
             authority: self.taker.to_account_info(),
         };
 
         let cpi_ctx = CpiContext::new(cpi_program, transfer_accounts);
 
-        transfer_checked(cpi_ctx, self.escrow.receive, self.mint_b.decimals)
+        // The taker covers any Token-2022 transfer fee so the recipient nets exactly `amount`
+        let gross_amount = gross_up_for_fee(&self.mint_b, amount)?;
+
+        transfer_checked(cpi_ctx, gross_amount, self.mint_b.decimals)
     }
 
-    // Helper function to transfer token A from vault to taker
-    fn transfer_a_to_taker(&self) -> Result<()> {
+    // Helper function to transfer token A from vault to taker, or to the taker's recipient
+    // Any Token-2022 transfer fee on token A is withheld from what the taker receives
+    fn transfer_a_to_taker(&self, amount: u64) -> Result<()> {
+        let native = native_mint::check_id(&self.mint_a.key());
+
+        // Native SOL always goes through the temporary wSOL account to be unwrapped
+        let to = match &self.recipient {
+            _ if native => self.unwrap_a.as_ref(),
+            Some(_) => self.recipient_ata_a.as_ref(),
+            None => self.taker_ata_a.as_ref(),
+        }
+        .ok_or(EscrowError::MissingTokenAccount)?;
+
         // Create CPI to token program for transferring token A
         let cpi_program = self.token_program.to_account_info();
 
@@ -133,7 +361,7 @@ impl<'info> Take<'info> {
         let transfer_accounts = TransferChecked {
             from: self.vault.to_account_info(),
             mint: self.mint_a.to_account_info(),
-            to: self.taker_ata_a.to_account_info(),
+            to: to.to_account_info(),
             authority: self.escrow.to_account_info(),
         };
 
@@ -153,11 +381,47 @@ impl<'info> Take<'info> {
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, transfer_accounts, signer_seeds);
 
         // Execute the transfer
-        transfer_checked(cpi_ctx, self.vault.amount, self.mint_a.decimals)
+        transfer_checked(cpi_ctx, amount, self.mint_a.decimals)?;
+
+        // Unwrap native SOL by closing the temporary wSOL account into the taker's wallet,
+        // which also returns the rent they paid for it, then forward it to the recipient
+        // if there is one
+        if native {
+            let close_accounts = CloseAccount {
+                account: to.to_account_info(),
+                destination: self.taker.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                close_accounts,
+                signer_seeds,
+            );
+            close_account(cpi_ctx)?;
+
+            if let Some(recipient) = &self.recipient {
+                let transfer_accounts = Transfer {
+                    from: self.taker.to_account_info(),
+                    to: recipient.to_account_info(),
+                };
+                let cpi_ctx =
+                    CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+                transfer(cpi_ctx, amount)?;
+            }
+        }
+
+        Ok(())
     }
 
     // Helper function to close the vault token account
     fn close_vault(&self) -> Result<()> {
+        // Token-2022 won't close an account holding withheld fees, so move them to the mint
+        harvest_withheld_fees(
+            self.token_program.to_account_info(),
+            self.mint_a.to_account_info(),
+            self.vault.to_account_info(),
+        )?;
+
         // Create CPI to token program for closing the account
         let cpi_program = self.token_program.to_account_info();
         let close_accounts = CloseAccount {
@@ -0,0 +1,217 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{
+        create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create,
+    },
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    error::EscrowError,
+    extensions::{gross_up_for_fee, harvest_withheld_fees},
+    state::{Bundle, Config},
+};
+
+// ===== TAKE BUNDLE INSTRUCTION ACCOUNTS =====
+// Settles every leg of a bundle atomically: the taker pays each requested leg to the
+// maker, minus the protocol fee, and receives each offered leg from its vault.
+//
+// remaining_accounts holds one triple per offered leg, then one group of four per
+// requested leg:
+//   offered:   [mint, vault, taker_ata]
+//   requested: [mint, taker_ata, maker_ata, fee_ata]
+// where fee_ata is the fee recipient's token account for the mint. Missing taker (offered),
+// maker and fee recipient (requested) token accounts are created on demand.
+// Offered mints with a Token-2022 transfer fee must be writable so the fees withheld in
+// their vaults can be harvested before the vaults are closed.
+#[derive(Accounts)]
+pub struct TakeBundle<'info> {
+    // The account taking the bundle offer - pays for any token accounts created on demand
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    // The original creator of the bundle - receives the rent of the vaults and bundle
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [b"bundle", maker.key().as_ref(), bundle.seed.to_le_bytes().as_ref()],
+        bump = bundle.bump,
+    )]
+    pub bundle: Account<'info, Bundle>,
+
+    // Global config holding the protocol fee and the pause switch
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    // Receives the protocol fee, must match the config
+    #[account(address = config.fee_recipient)]
+    pub fee_recipient: SystemAccount<'info>,
+
+    // Required programs
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeBundle<'info> {
+    pub fn settle_bundle(&mut self, remaining: &'info [AccountInfo<'info>]) -> Result<()> {
+        self.config.check_not_paused()?;
+        let offered_accounts = self.bundle.offered.len() * 3;
+        require!(
+            remaining.len() == offered_accounts + self.bundle.requested.len() * 4,
+            EscrowError::InvalidBundleAccounts
+        );
+        let (offered, requested) = remaining.split_at(offered_accounts);
+
+        // 1. Pay every requested leg from the taker to the maker, minus the protocol fee
+        for (leg, accounts) in self.bundle.requested.iter().zip(requested.chunks(4)) {
+            let [mint_info, taker_ata, maker_ata, fee_ata] = accounts else {
+                return err!(EscrowError::InvalidBundleAccounts);
+            };
+            let mint = self.load_mint(mint_info, &leg.mint)?;
+            let fee = self.config.fee_for(leg.amount)?;
+
+            self.create_ata_if_needed(maker_ata, &self.maker.to_account_info(), mint_info)?;
+            self.pay_leg(&mint, taker_ata, maker_ata, leg.amount - fee)?;
+            if fee > 0 {
+                self.create_ata_if_needed(
+                    fee_ata,
+                    &self.fee_recipient.to_account_info(),
+                    mint_info,
+                )?;
+                self.pay_leg(&mint, taker_ata, fee_ata, fee)?;
+            }
+        }
+
+        // 2. Release every offered leg from its vault to the taker and close the vault
+        let maker_key = self.maker.key();
+        let bundle_seed = self.bundle.seed.to_le_bytes();
+        let seeds = &[
+            b"bundle",
+            maker_key.as_ref(),
+            &bundle_seed[..],
+            &[self.bundle.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        for (leg, accounts) in self.bundle.offered.iter().zip(offered.chunks(3)) {
+            let [mint_info, vault, taker_ata] = accounts else {
+                return err!(EscrowError::InvalidBundleAccounts);
+            };
+            let mint = self.load_mint(mint_info, &leg.mint)?;
+            require_keys_eq!(
+                vault.key(),
+                get_associated_token_address_with_program_id(
+                    &self.bundle.key(),
+                    &leg.mint,
+                    &self.token_program.key()
+                ),
+                EscrowError::InvalidBundleAccounts
+            );
+            self.create_ata_if_needed(taker_ata, &self.taker.to_account_info(), mint_info)?;
+
+            let amount = InterfaceAccount::<TokenAccount>::try_from(vault)?.amount;
+            let transfer_accounts = TransferChecked {
+                from: vault.clone(),
+                mint: mint_info.clone(),
+                to: taker_ata.clone(),
+                authority: self.bundle.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                transfer_accounts,
+                signer_seeds,
+            );
+            transfer_checked(cpi_ctx, amount, mint.decimals)?;
+
+            harvest_withheld_fees(
+                self.token_program.to_account_info(),
+                mint_info.clone(),
+                vault.clone(),
+            )?;
+            let close_accounts = CloseAccount {
+                account: vault.clone(),
+                destination: self.maker.to_account_info(),
+                authority: self.bundle.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                close_accounts,
+                signer_seeds,
+            );
+            close_account(cpi_ctx)?;
+        }
+
+        Ok(())
+    }
+
+    // Transfer part of a requested leg from the taker
+    // The taker covers any Token-2022 transfer fee so the recipient nets `amount`
+    fn pay_leg(
+        &self,
+        mint: &InterfaceAccount<'info, Mint>,
+        taker_ata: &AccountInfo<'info>,
+        to: &AccountInfo<'info>,
+        amount: u64,
+    ) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: taker_ata.clone(),
+            mint: mint.to_account_info(),
+            to: to.clone(),
+            authority: self.taker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+        transfer_checked(cpi_ctx, gross_up_for_fee(mint, amount)?, mint.decimals)
+    }
+
+    // Check a leg's mint account and deserialize it
+    fn load_mint(
+        &self,
+        mint_info: &'info AccountInfo<'info>,
+        expected: &Pubkey,
+    ) -> Result<InterfaceAccount<'info, Mint>> {
+        require_keys_eq!(
+            mint_info.key(),
+            *expected,
+            EscrowError::InvalidBundleAccounts
+        );
+        require_keys_eq!(
+            *mint_info.owner,
+            self.token_program.key(),
+            EscrowError::InvalidBundleAccounts
+        );
+        InterfaceAccount::<Mint>::try_from(mint_info)
+    }
+
+    // Create `owner`'s associated token account for the mint unless it already exists
+    // The ATA program rejects any address that is not the owner's canonical ATA
+    fn create_ata_if_needed(
+        &self,
+        ata: &AccountInfo<'info>,
+        owner: &AccountInfo<'info>,
+        mint: &AccountInfo<'info>,
+    ) -> Result<()> {
+        let create_accounts = Create {
+            payer: self.taker.to_account_info(),
+            associated_token: ata.clone(),
+            authority: owner.clone(),
+            mint: mint.clone(),
+            system_program: self.system_program.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+        };
+        create_idempotent(CpiContext::new(
+            self.associated_token_program.to_account_info(),
+            create_accounts,
+        ))
+    }
+}
@@ -0,0 +1,316 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{MasterEditionAccount, Metadata, MetadataAccount},
+    token::spl_token::native_mint,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    error::EscrowError,
+    events::OfferTaken,
+    extensions::{check_mint_extensions, harvest_withheld_fees},
+    state::{Config, Escrow, OfferBook},
+};
+
+// ===== TAKE COLLECTION INSTRUCTION ACCOUNTS =====
+// Fills a collection offer, where the escrow's mint_b is a Metaplex collection mint.
+// The taker pays with any NFT whose metadata names that collection as verified, the same
+// check the marketplace's list runs. The NFT fills the whole offer, so the vault and escrow
+// are closed right away. Since an NFT can't be split, the protocol fee is taken from the
+// token A leg instead. The NFT and token A share one token program, and programmable NFTs
+// are not supported since they can't be moved with a plain token transfer.
+#[derive(Accounts)]
+pub struct TakeCollection<'info> {
+    // The account taking the offer - pays for any token accounts created on demand
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    // The maker who created the escrow - gets the vault and escrow rent back
+    #[account(
+        mut,
+        address = escrow.maker
+    )]
+    pub maker: SystemAccount<'info>,
+
+    // Where the maker routes the NFT, must match the escrow
+    /// CHECK: Safe because the address must match the escrow and it is only the authority
+    /// of maker_nft_ata
+    #[account(address = escrow.maker_recipient)]
+    pub maker_recipient: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        mint::token_program = token_program
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    // The NFT the taker pays with
+    #[account(
+        mint::token_program = token_program
+    )]
+    pub nft_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    // Metadata of the NFT, which must name the requested collection as verified
+    #[account(
+        seeds = [b"metadata", metadata_program.key().as_ref(), nft_mint.key().as_ref()],
+        seeds::program = metadata_program.key(),
+        bump,
+        constraint = metadata
+            .collection
+            .as_ref()
+            .is_some_and(|c| c.key == escrow.mint_b && c.verified) @ EscrowError::NotInCollection,
+    )]
+    pub metadata: Box<Account<'info, MetadataAccount>>,
+
+    // Master edition of the NFT, which proves it is an original and not a print or fungible token
+    #[account(
+        seeds = [b"metadata", metadata_program.key().as_ref(), nft_mint.key().as_ref(), b"edition"],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub master_edition: Box<Account<'info, MasterEditionAccount>>,
+
+    // Where the taker receives token A
+    // Left out for native SOL, which is unwrapped and sent to the taker's wallet
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_ata_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    // Temporary wSOL account owned by the escrow, only for native SOL
+    // The vault passes through it and it is closed within the same instruction to unwrap
+    #[account(
+        init,
+        payer = taker,
+        seeds = [b"unwrap", escrow.key().as_ref()],
+        bump,
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program
+    )]
+    pub unwrap_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    // Source of the taker's NFT
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_nft_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Where the maker's recipient receives the NFT
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = nft_mint,
+        associated_token::authority = maker_recipient,
+        associated_token::token_program = token_program
+    )]
+    pub maker_nft_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        has_one = mint_a,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Box<Account<'info, Escrow>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // The offer book page listing this escrow - the entry is removed as the offer is closed
+    #[account(
+        mut,
+        seeds = [
+            b"book",
+            escrow.mint_a.as_ref(),
+            escrow.mint_b.as_ref(),
+            escrow.book_page.to_le_bytes().as_ref()
+        ],
+        bump = book.bump,
+    )]
+    pub book: Box<Account<'info, OfferBook>>,
+
+    // Global config holding the protocol fee and the pause switch
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    // Receives the protocol fee, must match the config
+    #[account(
+        mut,
+        address = config.fee_recipient
+    )]
+    pub fee_recipient: SystemAccount<'info>,
+
+    // Where the protocol fee in token A is paid
+    // Created on demand; left out when token A is native SOL
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::authority = fee_recipient,
+        associated_token::token_program = token_program
+    )]
+    pub fee_ata_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    // Required programs
+    pub metadata_program: Program<'info, Metadata>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeCollection<'info> {
+    // - proof: Merkle proof of the taker's membership in the allowlist (empty when unused)
+    pub fn take_collection(&mut self, proof: &[[u8; 32]]) -> Result<()> {
+        self.config.check_not_paused()?;
+        require!(self.escrow.collection, EscrowError::NotCollectionOffer);
+        self.escrow.check_not_disputed()?;
+        self.escrow.check_taker(&self.taker.key(), proof)?;
+        require!(
+            Clock::get()?.unix_timestamp < self.escrow.expires_at,
+            EscrowError::OfferExpired
+        );
+        // A permanent delegate on the NFT could pull it back out of the maker's account
+        check_mint_extensions(&self.nft_mint)?;
+
+        emit!(OfferTaken {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            amount_a: self.vault.amount,
+            amount_b: 1,
+            remaining: 0,
+        });
+
+        // 1. The NFT from the taker to the maker's recipient
+        let transfer_accounts = TransferChecked {
+            from: self.taker_nft_ata.to_account_info(),
+            mint: self.nft_mint.to_account_info(),
+            to: self.maker_nft_ata.to_account_info(),
+            authority: self.taker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+        transfer_checked(cpi_ctx, 1, self.nft_mint.decimals)?;
+
+        // 2. Everything in the vault to the taker minus the protocol fee, then the vault is
+        // closed to the maker
+        let maker_key = self.maker.key();
+        let escrow_seed = self.escrow.seed.to_le_bytes();
+        let seeds = &[
+            b"escrow",
+            maker_key.as_ref(),
+            &escrow_seed[..],
+            &[self.escrow.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let fee = self.config.fee_for(self.vault.amount)?;
+        if native_mint::check_id(&self.mint_a.key()) {
+            // Native SOL is unwrapped into the taker's wallet, who then pays the fee from it
+            let unwrap_a = self
+                .unwrap_a
+                .as_ref()
+                .ok_or(EscrowError::MissingTokenAccount)?;
+            self.transfer_a(unwrap_a, self.vault.amount, signer_seeds)?;
+
+            let close_accounts = CloseAccount {
+                account: unwrap_a.to_account_info(),
+                destination: self.taker.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                close_accounts,
+                signer_seeds,
+            );
+            close_account(cpi_ctx)?;
+
+            if fee > 0 {
+                let transfer_accounts = Transfer {
+                    from: self.taker.to_account_info(),
+                    to: self.fee_recipient.to_account_info(),
+                };
+                let cpi_ctx =
+                    CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+                transfer(cpi_ctx, fee)?;
+            }
+        } else {
+            let taker_ata_a = self
+                .taker_ata_a
+                .as_ref()
+                .ok_or(EscrowError::MissingTokenAccount)?;
+            self.transfer_a(taker_ata_a, self.vault.amount - fee, signer_seeds)?;
+
+            if fee > 0 {
+                let fee_ata_a = self
+                    .fee_ata_a
+                    .as_ref()
+                    .ok_or(EscrowError::MissingTokenAccount)?;
+                self.transfer_a(fee_ata_a, fee, signer_seeds)?;
+            }
+        }
+
+        harvest_withheld_fees(
+            self.token_program.to_account_info(),
+            self.mint_a.to_account_info(),
+            self.vault.to_account_info(),
+        )?;
+        let close_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.maker.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            close_accounts,
+            signer_seeds,
+        );
+        close_account(cpi_ctx)?;
+
+        // 3. The offer is filled: unlist it and close the escrow to the maker
+        self.book.remove(&self.escrow.key())?;
+        self.escrow.close(self.maker.to_account_info())
+    }
+
+    // Transfer token A out of the vault, signed by the escrow
+    fn transfer_a(
+        &self,
+        to: &InterfaceAccount<'info, TokenAccount>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            to: to.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            transfer_accounts,
+            signer_seeds,
+        );
+        transfer_checked(cpi_ctx, amount, self.mint_a.decimals)
+    }
+}
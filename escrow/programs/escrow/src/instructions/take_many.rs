@@ -0,0 +1,329 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{get_associated_token_address_with_program_id, AssociatedToken},
+    token::spl_token::native_mint,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    constants::PRICE_SCALE,
+    error::EscrowError,
+    events::OfferTaken,
+    extensions::{gross_up_for_fee, harvest_withheld_fees, net_of_fee},
+    state::{Config, Escrow, OfferBook},
+};
+
+// ===== TAKE MANY INSTRUCTION ACCOUNTS =====
+// Fills several escrows for the same mint pair in one transaction, in the order given,
+// until the taker's token B budget is spent. The budget covers everything that leaves the
+// taker's account, including the protocol fee and any Token-2022 transfer fee on token B.
+// Offers on native SOL are rejected; use `take`, which wraps and unwraps it.
+//
+// remaining_accounts holds one group per escrow:
+//   [escrow, vault, maker_ata_b, maker, book]
+// where maker_ata_b is the maker recipient's token B account (it must already exist),
+// maker receives the rent once the escrow is fully filled, and book is the offer book
+// page listing the escrow.
+#[derive(Accounts)]
+pub struct TakeMany<'info> {
+    // The account taking the offers - pays for any token accounts created on demand
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    #[account(
+        mut,
+        mint::token_program = token_program
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mint::token_program = token_program
+    )]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    // Where the taker receives token A from every offer
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Source of the taker's token B
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(address = config.fee_recipient)]
+    pub fee_recipient: SystemAccount<'info>,
+
+    // Where the protocol fee in token B is paid, left out while the fee is zero
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = fee_recipient,
+        associated_token::token_program = token_program
+    )]
+    pub fee_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    // Required programs
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeMany<'info> {
+    // - budget_b: Most token B to pay across all offers, protocol and transfer fees included
+    // - max_price: Worst accepted price, token B per token A scaled by PRICE_SCALE
+    // - skip_unfillable: Skip offers that fail a check instead of failing the whole transaction
+    // - proofs: Allowlist proof for each offer, by position; missing or empty when unused
+    pub fn take_many(
+        &mut self,
+        remaining: &'info [AccountInfo<'info>],
+        budget_b: u64,
+        max_price: u64,
+        skip_unfillable: bool,
+        proofs: &[Vec<[u8; 32]>],
+    ) -> Result<()> {
+        self.config.check_not_paused()?;
+        require!(
+            !native_mint::check_id(&self.mint_a.key())
+                && !native_mint::check_id(&self.mint_b.key()),
+            EscrowError::NativeMintUnsupported
+        );
+        require!(
+            !remaining.is_empty() && remaining.chunks_exact(5).remainder().is_empty(),
+            EscrowError::InvalidOfferAccounts
+        );
+
+        let mut budget_left = budget_b;
+        for (i, accounts) in remaining.chunks(5).enumerate() {
+            if budget_left == 0 {
+                break;
+            }
+            let proof = proofs.get(i).map(Vec::as_slice).unwrap_or_default();
+
+            // Every check runs before anything moves, so skipping an offer leaves no trace
+            let (escrow, amount_a, amount_b, cost) =
+                match self.check_offer(accounts, budget_left, max_price, proof) {
+                    Ok(fill) => fill,
+                    Err(_) if skip_unfillable => continue,
+                    Err(err) => return Err(err),
+                };
+
+            self.settle(accounts, escrow, amount_a, amount_b)?;
+            budget_left = budget_left.checked_sub(cost).ok_or(EscrowError::Overflow)?;
+        }
+
+        Ok(())
+    }
+
+    // Load one offer and work out how much of it the budget fills
+    // Returns the escrow with the token A and token B amounts of the fill, and what the
+    // fill costs the taker in token B once transfer fees are added
+    fn check_offer(
+        &self,
+        accounts: &'info [AccountInfo<'info>],
+        budget_left: u64,
+        max_price: u64,
+        proof: &[[u8; 32]],
+    ) -> Result<(Account<'info, Escrow>, u64, u64, u64)> {
+        let [escrow_info, vault, maker_ata_b, maker, book] = accounts else {
+            return err!(EscrowError::InvalidOfferAccounts);
+        };
+
+        let escrow = Account::<Escrow>::try_from(escrow_info)?;
+        let token_program = self.token_program.key();
+        require!(
+            escrow.mint_a == self.mint_a.key()
+                && escrow.mint_b == self.mint_b.key()
+                && escrow.maker == maker.key()
+                && vault.key()
+                    == get_associated_token_address_with_program_id(
+                        &escrow_info.key(),
+                        &escrow.mint_a,
+                        &token_program,
+                    )
+                && maker_ata_b.key()
+                    == get_associated_token_address_with_program_id(
+                        &escrow.maker_recipient,
+                        &escrow.mint_b,
+                        &token_program,
+                    ),
+            EscrowError::InvalidOfferAccounts
+        );
+
+        let book = Account::<OfferBook>::try_from(book)?;
+        require!(
+            book.mint_a == escrow.mint_a
+                && book.mint_b == escrow.mint_b
+                && book.page == escrow.book_page,
+            EscrowError::InvalidOfferAccounts
+        );
+
+        escrow.check_not_disputed()?;
+        escrow.check_not_collection()?;
+        escrow.check_taker(&self.taker.key(), proof)?;
+        require!(
+            Clock::get()?.unix_timestamp < escrow.expires_at,
+            EscrowError::OfferExpired
+        );
+
+        let vault_amount = InterfaceAccount::<TokenAccount>::try_from(vault)?.amount;
+        let max_b = (max_price as u128)
+            .checked_mul(vault_amount as u128)
+            .ok_or(EscrowError::Overflow)?;
+        require!(
+            (escrow.remaining as u128) * (PRICE_SCALE as u128) <= max_b,
+            EscrowError::PriceTooHigh
+        );
+
+        // Fees round up, so a fill sized from the net budget can overshoot by a unit or two;
+        // the fill is trimmed by the overshoot until its cost fits the budget. Every pass
+        // shrinks the fill and an empty fill costs nothing, so the loop ends.
+        let mut amount_b = escrow.remaining.min(net_of_fee(&self.mint_b, budget_left)?);
+        let mut cost = self.cost_of(amount_b)?;
+        while cost > budget_left {
+            amount_b = amount_b.saturating_sub(cost - budget_left);
+            cost = self.cost_of(amount_b)?;
+        }
+        let amount_a = escrow.amount_a_for(vault_amount, amount_b)?;
+        require!(amount_a > 0, EscrowError::FillTooSmall);
+
+        Ok((escrow, amount_a, amount_b, cost))
+    }
+
+    // Token B leaving the taker for a fill of `amount_b`: the maker's share and the
+    // protocol fee, each grossed up for any Token-2022 transfer fee as transfer_b sends them
+    fn cost_of(&self, amount_b: u64) -> Result<u64> {
+        let fee = self.config.fee_for(amount_b)?;
+        let mut cost = gross_up_for_fee(&self.mint_b, amount_b - fee)?;
+        if fee > 0 {
+            cost = cost
+                .checked_add(gross_up_for_fee(&self.mint_b, fee)?)
+                .ok_or(EscrowError::Overflow)?;
+        }
+        Ok(cost)
+    }
+
+    // Pay the maker, release token A to the taker and record the fill,
+    // closing the vault and the escrow if it is now fully filled
+    fn settle(
+        &self,
+        accounts: &'info [AccountInfo<'info>],
+        mut escrow: Account<'info, Escrow>,
+        amount_a: u64,
+        amount_b: u64,
+    ) -> Result<()> {
+        let [escrow_info, vault, maker_ata_b, maker, book] = accounts else {
+            return err!(EscrowError::InvalidOfferAccounts);
+        };
+
+        // 1. Token B from the taker to the maker's recipient, minus the protocol fee
+        let fee = self.config.fee_for(amount_b)?;
+        self.transfer_b(maker_ata_b, amount_b - fee)?;
+        if fee > 0 {
+            let fee_ata_b = self
+                .fee_ata_b
+                .as_ref()
+                .ok_or(EscrowError::MissingTokenAccount)?;
+            self.transfer_b(&fee_ata_b.to_account_info(), fee)?;
+        }
+
+        // 2. Token A from the vault to the taker, signed by the escrow
+        let maker_key = maker.key();
+        let escrow_seed = escrow.seed.to_le_bytes();
+        let seeds = &[
+            b"escrow",
+            maker_key.as_ref(),
+            &escrow_seed[..],
+            &[escrow.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let transfer_accounts = TransferChecked {
+            from: vault.clone(),
+            mint: self.mint_a.to_account_info(),
+            to: self.taker_ata_a.to_account_info(),
+            authority: escrow_info.clone(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            transfer_accounts,
+            signer_seeds,
+        );
+        transfer_checked(cpi_ctx, amount_a, self.mint_a.decimals)?;
+
+        // 3. Record the fill
+        escrow.remaining -= amount_b;
+        escrow.filled = escrow
+            .filled
+            .checked_add(amount_b)
+            .ok_or(EscrowError::Overflow)?;
+        emit!(OfferTaken {
+            escrow: escrow_info.key(),
+            taker: self.taker.key(),
+            amount_a,
+            amount_b,
+            remaining: escrow.remaining,
+        });
+
+        if escrow.remaining > 0 {
+            return escrow.exit(&crate::ID);
+        }
+
+        // 4. Fully filled: unlist it and close the vault and escrow to the maker
+        let mut book = Account::<OfferBook>::try_from(book)?;
+        book.remove(&escrow_info.key())?;
+        book.exit(&crate::ID)?;
+
+        harvest_withheld_fees(
+            self.token_program.to_account_info(),
+            self.mint_a.to_account_info(),
+            vault.clone(),
+        )?;
+        let close_accounts = CloseAccount {
+            account: vault.clone(),
+            destination: maker.clone(),
+            authority: escrow_info.clone(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            close_accounts,
+            signer_seeds,
+        );
+        close_account(cpi_ctx)?;
+
+        escrow.close(maker.clone())
+    }
+
+    // Transfer token B from the taker, with the taker covering any Token-2022 transfer fee
+    fn transfer_b(&self, to: &AccountInfo<'info>, amount: u64) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to: to.clone(),
+            authority: self.taker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+
+        let gross_amount = gross_up_for_fee(&self.mint_b, amount)?;
+        transfer_checked(cpi_ctx, gross_amount, self.mint_b.decimals)
+    }
+}
@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::MAX_FEE_BPS, error::EscrowError, state::Config};
+
+// ===== UPDATE CONFIG INSTRUCTION ACCOUNTS =====
+// Admin-only changes to the global config
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin @ EscrowError::Unauthorized,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> UpdateConfig<'info> {
+    // Change the protocol fee and where it is paid, up to MAX_FEE_BPS
+    pub fn update_fee(&mut self, fee_bps: u16, fee_recipient: Pubkey) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, EscrowError::InvalidFee);
+
+        self.config.fee_bps = fee_bps;
+        self.config.fee_recipient = fee_recipient;
+        Ok(())
+    }
+
+    // Stop new offers and takes, e.g. while an incident is investigated
+    pub fn pause(&mut self) -> Result<()> {
+        self.config.paused = true;
+        Ok(())
+    }
+
+    // Resume normal operation after a pause
+    pub fn unpause(&mut self) -> Result<()> {
+        self.config.paused = false;
+        Ok(())
+    }
+
+    // Offer the admin role to another wallet, which only takes over once it accepts
+    // Offering it again replaces the pending offer
+    pub fn set_admin(&mut self, new_admin: Pubkey) -> Result<()> {
+        self.config.pending_admin = Some(new_admin);
+        Ok(())
+    }
+}
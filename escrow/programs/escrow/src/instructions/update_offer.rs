@@ -0,0 +1,230 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::spl_token::native_mint,
+    token_interface::{
+        close_account, sync_native, transfer_checked, CloseAccount, Mint, SyncNative, TokenAccount,
+        TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    error::EscrowError,
+    events::OfferUpdated,
+    extensions::gross_up_for_fee,
+    state::{Config, Escrow},
+};
+
+// ===== UPDATE OFFER INSTRUCTION ACCOUNTS =====
+// Lets the maker amend an open offer in place instead of refunding and making it again:
+// change the amount of token B asked for, top up or withdraw part of the vault,
+// and push the expiry further out.
+#[derive(Accounts)]
+pub struct UpdateOffer<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(
+        mint::token_program = token_program
+    )]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    // Source of a top up and destination of a withdrawal
+    // Left out for native SOL, which is wrapped and unwrapped straight from the maker's wallet
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        has_one = mint_a,
+        has_one = maker,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    // Temporary wSOL account owned by the escrow, only for withdrawing native SOL
+    // The withdrawal passes through it and it is closed within the same instruction to unwrap
+    #[account(
+        init,
+        payer = maker,
+        seeds = [b"unwrap", escrow.key().as_ref()],
+        bump,
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program
+    )]
+    pub unwrap_a: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // Global config, checked so no offer is amended while the program is paused
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> UpdateOffer<'info> {
+    // - receive: New total amount of token B asked for, including what is already filled
+    // - top_up: Amount of token A to add to the vault
+    // - withdraw: Amount of token A to take back out of the vault
+    // - expires_at: New expiry, which can only move later
+    pub fn update_offer(
+        &mut self,
+        receive: Option<u64>,
+        top_up: u64,
+        withdraw: u64,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        self.config.check_not_paused()?;
+        self.escrow.check_not_disputed()?;
+
+        if let Some(receive) = receive {
+            // A collection offer always asks for exactly one NFT
+            self.escrow.check_not_collection()?;
+            // The new price must still leave something to fill
+            require!(receive > self.escrow.filled, EscrowError::InvalidAmount);
+            self.escrow.receive = receive;
+            self.escrow.remaining = receive - self.escrow.filled;
+        }
+
+        if let Some(expires_at) = expires_at {
+            require!(
+                expires_at > self.escrow.expires_at,
+                EscrowError::InvalidExpiry
+            );
+            self.escrow.expires_at = expires_at;
+        }
+
+        if top_up > 0 {
+            self.top_up(top_up)?;
+            self.vault.reload()?;
+        }
+
+        if withdraw > 0 {
+            // Withdrawing everything would leave an offer nobody can fill; use refund instead
+            require!(withdraw < self.vault.amount, EscrowError::InvalidAmount);
+            self.withdraw(withdraw)?;
+        }
+
+        self.vault.reload()?;
+        emit!(OfferUpdated {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            receive: self.escrow.receive,
+            remaining: self.escrow.remaining,
+            deposit: self.vault.amount,
+            expires_at: self.escrow.expires_at,
+        });
+
+        Ok(())
+    }
+
+    // Add more token A to the vault
+    fn top_up(&mut self, amount: u64) -> Result<()> {
+        // Native SOL is wrapped by sending lamports to the wSOL vault and syncing its balance
+        if native_mint::check_id(&self.mint_a.key()) {
+            let transfer_accounts = Transfer {
+                from: self.maker.to_account_info(),
+                to: self.vault.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+            transfer(cpi_ctx, amount)?;
+
+            let sync_accounts = SyncNative {
+                account: self.vault.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), sync_accounts);
+            return sync_native(cpi_ctx);
+        }
+
+        let maker_ata_a = self
+            .maker_ata_a
+            .as_ref()
+            .ok_or(EscrowError::MissingTokenAccount)?;
+
+        let transfer_accounts = TransferChecked {
+            from: maker_ata_a.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.maker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+
+        // Token-2022 transfer fees are added on top so the vault grows by the full amount
+        let gross_amount = gross_up_for_fee(&self.mint_a, amount)?;
+        transfer_checked(cpi_ctx, gross_amount, self.mint_a.decimals)
+    }
+
+    // Move part of the vault back to the maker
+    fn withdraw(&mut self, amount: u64) -> Result<()> {
+        let native = native_mint::check_id(&self.mint_a.key());
+
+        // Native SOL goes through the temporary wSOL account to be unwrapped
+        let to = if native {
+            self.unwrap_a.as_ref()
+        } else {
+            self.maker_ata_a.as_ref()
+        }
+        .ok_or(EscrowError::MissingTokenAccount)?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        let transfer_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            to: to.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            transfer_accounts,
+            &signer_seeds,
+        );
+        transfer_checked(cpi_ctx, amount, self.mint_a.decimals)?;
+
+        // Unwrap native SOL by closing the temporary wSOL account into the maker's wallet,
+        // which also returns the rent they paid for it
+        if native {
+            let close_accounts = CloseAccount {
+                account: to.to_account_info(),
+                destination: self.maker.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                close_accounts,
+                &signer_seeds,
+            );
+            close_account(cpi_ctx)?;
+        }
+
+        Ok(())
+    }
+}
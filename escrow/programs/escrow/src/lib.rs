@@ -2,9 +2,16 @@
 
 use anchor_lang::prelude::*;
 
+pub mod constants;
+pub mod error;
+pub mod events;
+pub mod extensions;
 pub mod instructions;
 pub mod state;
 
+pub use constants::*;
+pub use error::*;
+pub use events::*;
 pub use instructions::*;
 pub use state::*;
 
@@ -18,69 +25,333 @@ declare_id!("7RwCakEJihTYJDvpQHkBnXZBuTgX9x51Wrg5Ut4DMRxm");
 pub mod escrow {
     use super::*;
 
+    // Creates the global config holding the admin and the protocol fee
+    // Only the program's upgrade authority can call it, and becomes the admin
+    // - fee_bps: Fee taken from the token B leg of every take, capped at MAX_FEE_BPS
+    // - fee_recipient: Wallet that receives the fee
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        fee_bps: u16,
+        fee_recipient: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.initialize_config(fee_bps, fee_recipient, &ctx.bumps)
+    }
+
+    // Lets the admin change the protocol fee and its recipient
+    pub fn update_fee(
+        ctx: Context<UpdateConfig>,
+        fee_bps: u16,
+        fee_recipient: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.update_fee(fee_bps, fee_recipient)
+    }
+
+    // Emergency stop: the admin blocks new offers and takes until unpause
+    // Refunds keep working so makers can always withdraw their deposits
+    pub fn pause(ctx: Context<UpdateConfig>) -> Result<()> {
+        ctx.accounts.pause()
+    }
+
+    // Lets the admin lift a pause
+    pub fn unpause(ctx: Context<UpdateConfig>) -> Result<()> {
+        ctx.accounts.unpause()
+    }
+
+    // Lets the admin offer the admin role to another wallet
+    pub fn set_admin(ctx: Context<UpdateConfig>, new_admin: Pubkey) -> Result<()> {
+        ctx.accounts.set_admin(new_admin)
+    }
+
+    // The wallet offered the admin role signs to take it over
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        ctx.accounts.accept_admin()
+    }
+
     // The 'make' instruction creates a new escrow trade
     // - seed: A unique value to derive the escrow PDA
+    // - book_page: Offer book page for the mint pair to list the escrow on
     // - deposit: Amount of token A to deposit into escrow
     // - receive: Amount of token B expected in return
-    pub fn make(ctx: Context<Make>, seed: u64, deposit: u64, receive: u64) -> Result<()> {
+    // - collection: mint_b is a collection and any verified NFT from it fills the offer (receive = 1)
+    // - expires_at: Unix timestamp after which the offer can no longer be taken
+    // - taker: Optional wallet that is the only one allowed to take the offer
+    // - allowlist: Optional Merkle root of the wallets allowed to take the offer
+    // - arbitration: Optional arbiter who settles disputes with the named taker
+    // - maker_recipient: Optional wallet that receives token B instead of the maker
+    #[allow(clippy::too_many_arguments)]
+    pub fn make(
+        ctx: Context<Make>,
+        seed: u64,
+        book_page: u32,
+        deposit: u64,
+        receive: u64,
+        collection: bool,
+        expires_at: i64,
+        taker: Option<Pubkey>,
+        allowlist: Option<[u8; 32]>,
+        arbitration: Option<ArbiterTerms>,
+        maker_recipient: Option<Pubkey>,
+    ) -> Result<()> {
         // Initialize the escrow data
-        ctx.accounts.init_escrow(seed, receive, &ctx.bumps)?;
+        ctx.accounts.init_escrow(
+            seed,
+            book_page,
+            receive,
+            collection,
+            expires_at,
+            taker,
+            allowlist,
+            arbitration,
+            maker_recipient,
+            &ctx.bumps,
+        )?;
+        // List the offer so takers can find it by mint pair
+        ctx.accounts.list_offer(&ctx.bumps)?;
         // Deposit the tokens from maker into the vault
         ctx.accounts.deposit(deposit)
     }
 
     // This instruction will allow a taker to accept the trade and complete the escrow
-    pub fn take(ctx: Context<Take>, _seed: u64) -> Result<()> {
-        ctx.accounts.execute_trade()
+    // - max_amount_b: Most token B the taker agrees to pay, protocol and transfer fees included
+    // - min_amount_a: Least token A the taker agrees to receive
+    // - proof: Merkle proof that the taker is in the offer's allowlist (empty if there is none)
+    // The limits guard against the maker amending the offer while the take is in flight.
+    // Token A goes to the optional recipient account instead of the taker when one is passed,
+    // and token B always goes to the maker_recipient picked at make time
+    pub fn take(
+        ctx: Context<Take>,
+        _seed: u64,
+        max_amount_b: u64,
+        min_amount_a: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.execute_trade(max_amount_b, min_amount_a, &proof)
+    }
+
+    // This instruction will allow a taker to fill only part of the offer
+    // - amount_b: Amount of token B to pay, receiving a pro-rata amount of token A
+    // - min_amount_a: Least token A the taker agrees to receive for it
+    // The vault and escrow are closed once the offer is fully filled
+    pub fn take_partial(
+        ctx: Context<Take>,
+        _seed: u64,
+        amount_b: u64,
+        min_amount_a: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.execute_partial_trade(amount_b, min_amount_a, &proof)
+    }
+
+    // Fills several offers for the same mint pair in order, up to a token B budget
+    // - budget_b: Most token B to pay across all offers, protocol and transfer fees included
+    // - max_price: Worst accepted price, token B per token A scaled by PRICE_SCALE
+    // - skip_unfillable: Skip offers that fail a check instead of failing the whole transaction
+    // - proofs: Allowlist proof for each offer in order, empty for offers without an allowlist
+    // Each offer is passed through remaining_accounts as [escrow, vault, maker_ata_b, maker, book]
+    // Offers on native SOL are rejected, take them one by one with take instead
+    pub fn take_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TakeMany<'info>>,
+        budget_b: u64,
+        max_price: u64,
+        skip_unfillable: bool,
+        proofs: Vec<Vec<[u8; 32]>>,
+    ) -> Result<()> {
+        ctx.accounts.take_many(
+            ctx.remaining_accounts,
+            budget_b,
+            max_price,
+            skip_unfillable,
+            &proofs,
+        )
+    }
+
+    // Fills a collection offer with any verified NFT from the requested collection
+    // The protocol fee is taken from token A, and the NFT must belong to the same token
+    // program as token A since both move through the one token_program account
+    // - proof: Merkle proof that the taker is in the offer's allowlist (empty if there is none)
+    pub fn take_collection(ctx: Context<TakeCollection>, proof: Vec<[u8; 32]>) -> Result<()> {
+        ctx.accounts.take_collection(&proof)
     }
 
-    // TODO: Add the 'refund' instruction
     // This instruction will allow the maker to reclaim their tokens if no taker accepts
     pub fn refund(ctx: Context<Refund>) -> Result<()> {
         ctx.accounts.refund_and_close_vault()
     }
-}
 
-// ===== EXAMPLE ACCOUNT STRUCTURES =====
-// These are not used in the current implementation but demonstrate
-// how account validation works in Anchor
-
-#[derive(Accounts)]
-pub struct InitializeEscrow<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-
-    #[account(
-        init,
-        space = 8 + Escrow::INIT_SPACE,
-        payer = payer
-    )]
-    pub escrow: Account<'info, Escrow>,
-    pub system_program: Program<'info, System>,
-}
+    // Permissionless crank: once an offer has expired anyone can return the remaining
+    // token A to the maker and close the vault and escrow, with the rent going to the maker
+    pub fn refund_expired(ctx: Context<RefundExpired>) -> Result<()> {
+        ctx.accounts.refund_expired()
+    }
 
-#[derive(Accounts)]
-pub struct CloseEscrow<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
+    // Lets the maker amend an open offer in place and emits OfferUpdated
+    // - receive: New total amount of token B asked for, including what is already filled
+    // - top_up: Amount of token A to add to the vault
+    // - withdraw: Amount of token A to take back out of the vault
+    // - expires_at: New expiry, which can only be extended
+    pub fn update_offer(
+        ctx: Context<UpdateOffer>,
+        receive: Option<u64>,
+        top_up: u64,
+        withdraw: u64,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        ctx.accounts.update_offer(receive, top_up, withdraw, expires_at)
+    }
 
-    #[account(
-        mut,
-        close = payer, // close account and return lamports to payer
-    )]
-    pub escrow: Account<'info, Escrow>,
-}
+    // A taker proposes a different amount of token B for a fixed amount of token A
+    // - amount_a: Amount of token A the maker must release on accept
+    // - amount_b: Amount of token B escrowed in the counter vault
+    pub fn make_counter(ctx: Context<MakeCounter>, amount_a: u64, amount_b: u64) -> Result<()> {
+        ctx.accounts.make_counter(amount_a, amount_b, &ctx.bumps)
+    }
 
-#[derive(Accounts)]
-pub struct Update<'info> {
-    #[account(mut)]
-    pub escrow: Account<'info, Escrow>,
-}
+    // The maker accepts a counter-offer, settling both vaults atomically
+    pub fn accept_counter(ctx: Context<AcceptCounter>) -> Result<()> {
+        ctx.accounts.accept_counter()
+    }
+
+    // The maker rejects a counter-offer, returning the token B to the taker
+    pub fn reject_counter(ctx: Context<CloseCounter>) -> Result<()> {
+        ctx.accounts.reject()
+    }
 
-// NOTE: This is a placeholder struct and is replaced by the actual
-// Escrow struct defined in state.rs
-#[account]
-#[derive(InitSpace)]
-pub struct Escrow {
-    count: u8,
+    // The taker withdraws an unaccepted counter-offer at any time
+    pub fn withdraw_counter(ctx: Context<CloseCounter>) -> Result<()> {
+        ctx.accounts.withdraw()
+    }
+
+    // Settles an order the maker signed off-chain, without an escrow PDA or vault
+    // The previous instruction must be an Ed25519 signature check of order.message() by the maker
+    pub fn fill_order(ctx: Context<FillOrder>, order: SignedOrder) -> Result<()> {
+        ctx.accounts.fill_order(&order, &ctx.bumps)
+    }
+
+    // Lets the maker cancel a signed order by burning its nonce
+    pub fn cancel_order(ctx: Context<CancelOrder>, nonce: u64) -> Result<()> {
+        ctx.accounts.cancel_order(nonce, &ctx.bumps)
+    }
+
+    // The 'make_bundle' instruction creates an offer trading several mints for several mints
+    // - seed: A unique value to derive the bundle PDA
+    // - offered: Mints and amounts the maker deposits, one vault per mint
+    // - requested: Mints and amounts the maker expects in return
+    // Token accounts for the offered legs, then the requested mints, are passed through
+    // remaining_accounts. Every leg must belong to the token program passed in.
+    pub fn make_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MakeBundle<'info>>,
+        seed: u64,
+        offered: Vec<Leg>,
+        requested: Vec<Leg>,
+    ) -> Result<()> {
+        ctx.accounts.init_bundle(seed, offered, requested, &ctx.bumps)?;
+        ctx.accounts.deposit_legs(ctx.remaining_accounts)
+    }
+
+    // Settles every leg of a bundle atomically and closes it
+    pub fn take_bundle<'info>(ctx: Context<'_, '_, 'info, 'info, TakeBundle<'info>>) -> Result<()> {
+        ctx.accounts.settle_bundle(ctx.remaining_accounts)
+    }
+
+    // Lets the maker cancel a bundle and get every offered leg back
+    pub fn refund_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RefundBundle<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.refund_and_close_vaults(ctx.remaining_accounts)
+    }
+
+    // The 'make_milestones' instruction creates an escrow released to the payee in stages
+    // - seed: A unique value to derive the milestone escrow PDA
+    // - payee: Wallet that receives each released tranche
+    // - milestones: Amount and description hash of every tranche, all deposited up front
+    // - deadline: Unix timestamp after which the payer can refund unreleased tranches alone
+    // - arbitration: Optional arbiter who settles disputes between payer and payee
+    pub fn make_milestones(
+        ctx: Context<MakeMilestones>,
+        seed: u64,
+        payee: Pubkey,
+        milestones: Vec<MilestoneTerms>,
+        deadline: i64,
+        arbitration: Option<ArbiterTerms>,
+    ) -> Result<()> {
+        ctx.accounts.make_milestones(
+            seed,
+            payee,
+            milestones,
+            deadline,
+            arbitration,
+            &ctx.bumps,
+        )
+    }
+
+    // The payer approves a milestone, releasing its tranche to the payee
+    // - index: Position of the milestone in the escrow's list
+    pub fn release_milestone(ctx: Context<ReleaseMilestone>, index: u8) -> Result<()> {
+        ctx.accounts.release_milestone(index)
+    }
+
+    // The payer or the payee signs off on refunding the unreleased tranches
+    pub fn approve_milestone_refund(ctx: Context<ApproveMilestoneRefund>) -> Result<()> {
+        ctx.accounts.approve_refund()
+    }
+
+    // Returns the unreleased tranches to the payer, once both parties approved or after the deadline
+    pub fn refund_milestones(ctx: Context<RefundMilestones>) -> Result<()> {
+        ctx.accounts.refund_milestones()
+    }
+
+    // The maker or the named taker freezes an arbitrated escrow until the arbiter rules
+    // Only offers the taker has partly filled can be disputed, and once DISPUTE_TIMEOUT
+    // passes without a ruling the maker can refund again
+    pub fn raise_dispute(ctx: Context<RaiseDispute>) -> Result<()> {
+        ctx.accounts.raise_dispute()
+    }
+
+    // The arbiter splits a disputed escrow's vault between maker and taker and closes it
+    // - maker_share_bps: Share of the vault, after the arbitration fee, that goes to the maker
+    pub fn resolve_dispute(ctx: Context<ResolveDispute>, maker_share_bps: u16) -> Result<()> {
+        ctx.accounts.resolve_dispute(maker_share_bps)
+    }
+
+    // The payer or the payee freezes an arbitrated milestone escrow until the arbiter rules
+    pub fn raise_milestone_dispute(ctx: Context<RaiseMilestoneDispute>) -> Result<()> {
+        ctx.accounts.raise_dispute()
+    }
+
+    // The arbiter splits the unreleased tranches between payer and payee and closes the escrow
+    // - payer_share_bps: Share of the vault, after the arbitration fee, that goes to the payer
+    pub fn resolve_milestone_dispute(
+        ctx: Context<ResolveMilestoneDispute>,
+        payer_share_bps: u16,
+    ) -> Result<()> {
+        ctx.accounts.resolve_dispute(payer_share_bps)
+    }
+
+    // The 'make_vesting' instruction locks tokens that vest linearly to a beneficiary
+    // - seed: A unique value to derive the vesting PDA
+    // - beneficiary: Wallet that claims the vested tokens
+    // - total: Amount deposited now and fully vested at the end of the schedule
+    // - schedule: Start, cliff and end of the schedule as unix timestamps
+    // - revoker: Optional wallet that can claw back the unvested part
+    pub fn make_vesting(
+        ctx: Context<MakeVesting>,
+        seed: u64,
+        beneficiary: Pubkey,
+        total: u64,
+        schedule: VestingSchedule,
+        revoker: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts
+            .make_vesting(seed, beneficiary, total, schedule, revoker, &ctx.bumps)
+    }
+
+    // The beneficiary claims everything vested so far
+    pub fn claim_vesting(ctx: Context<ClaimVesting>) -> Result<()> {
+        ctx.accounts.claim()
+    }
+
+    // The revoker stops the schedule: vested tokens go to the beneficiary, the rest back to the funder
+    pub fn revoke_vesting(ctx: Context<RevokeVesting>) -> Result<()> {
+        ctx.accounts.revoke()
+    }
 }
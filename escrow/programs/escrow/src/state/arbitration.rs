@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::{DISPUTE_TIMEOUT, MAX_ARBITER_FEE_BPS},
+    error::EscrowError,
+};
+
+// ===== ARBITRATION =====
+// Optional third party named when an escrow is made. Either side can raise a dispute,
+// which freezes the escrow until the arbiter splits the vault between the two sides.
+// An arbiter who hasn't ruled within DISPUTE_TIMEOUT no longer blocks refunds.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Arbitration {
+    pub arbiter: Pubkey,  // Wallet that settles a dispute by splitting the vault
+    pub fee_bps: u16,     // Cut of the vault paid to the arbiter when resolving, in basis points
+    pub disputed: bool,   // Set by either side; freezes the escrow until the arbiter resolves it
+    pub disputed_at: i64, // Unix timestamp the dispute was raised at
+}
+
+impl Arbitration {
+    // Check the terms picked at make time
+    // - parties: The two sides of the escrow, neither of which can be the arbiter
+    pub fn new(terms: ArbiterTerms, parties: [Pubkey; 2]) -> Result<Self> {
+        require!(
+            terms.fee_bps <= MAX_ARBITER_FEE_BPS && !parties.contains(&terms.arbiter),
+            EscrowError::InvalidArbitration
+        );
+        Ok(Self {
+            arbiter: terms.arbiter,
+            fee_bps: terms.fee_bps,
+            disputed: false,
+            disputed_at: 0,
+        })
+    }
+
+    // Freeze the escrow until the arbiter rules or the timeout passes
+    pub fn raise(&mut self, now: i64) -> Result<()> {
+        require!(!self.disputed, EscrowError::Disputed);
+        self.disputed = true;
+        self.disputed_at = now;
+        Ok(())
+    }
+
+    // Whether a dispute blocks refunds at `now`, i.e. it is open and the arbiter is in time
+    pub fn blocks_refund(&self, now: i64) -> bool {
+        self.disputed && now < self.disputed_at.saturating_add(DISPUTE_TIMEOUT)
+    }
+
+    // Split a vault balance into (arbiter fee, first side's share, second side's share)
+    // - first_bps: Share of what is left after the fee that goes to the first side
+    pub fn split(&self, amount: u64, first_bps: u16) -> Result<(u64, u64, u64)> {
+        require!(first_bps <= 10_000, EscrowError::InvalidAmount);
+
+        let fee = (amount as u128)
+            .checked_mul(self.fee_bps as u128)
+            .ok_or(EscrowError::Overflow)?
+            / 10_000;
+        let rest = amount as u128 - fee;
+        let first = rest
+            .checked_mul(first_bps as u128)
+            .ok_or(EscrowError::Overflow)?
+            / 10_000;
+
+        Ok((fee as u64, first as u64, (rest - first) as u64))
+    }
+}
+
+// Arbiter and fee as passed to make and make_milestones
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ArbiterTerms {
+    pub arbiter: Pubkey,
+    pub fee_bps: u16,
+}
@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_BUNDLE_LEGS;
+
+// ===== BUNDLE STATE ACCOUNT =====
+// A bundle offer escrows several mints at once and asks for several mints in return.
+// Each offered leg is held in its own vault (an ATA owned by the bundle PDA) and
+// the whole bundle settles atomically in a single take.
+#[account]
+#[derive(InitSpace)]
+pub struct Bundle {
+    pub seed: u64,     // Random seed used for PDA derivation
+    pub maker: Pubkey, // The public key of the bundle creator
+    // Mints and amounts the maker deposited into the vaults
+    #[max_len(MAX_BUNDLE_LEGS)]
+    pub offered: Vec<Leg>,
+    // Mints and amounts the maker wants in return
+    #[max_len(MAX_BUNDLE_LEGS)]
+    pub requested: Vec<Leg>,
+    pub bump: u8,      // Bump seed for PDA - needed for signing during take and refund
+}
+
+// One side of a bundle trade: a mint and the amount of it being exchanged
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Leg {
+    pub mint: Pubkey, // Mint of the token (NFT or fungible)
+    pub amount: u64,  // Amount of the token, 1 for an NFT
+}
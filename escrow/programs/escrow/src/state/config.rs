@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::error::EscrowError;
+
+// ===== CONFIG STATE ACCOUNT =====
+// Global settings for the escrow service, stored in a single PDA seeded by b"config"
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,                 // Can update the fee, pause the program and hand over the admin role
+    pub pending_admin: Option<Pubkey>, // Wallet the admin role was offered to, until it accepts
+    pub fee_bps: u16,                  // Protocol fee taken from the token B leg of every take (token A for collection offers), in basis points
+    pub fee_recipient: Pubkey,         // Wallet that receives the protocol fee (or owns the fee token accounts)
+    pub paused: bool,                  // Emergency stop: no new offers and no takes while set
+    pub bump: u8,                      // Bump seed for the config PDA
+}
+
+impl Config {
+    // Rejects new offers and takes while the admin has the program paused
+    // Refunds stay open so makers can always get their deposits back
+    pub fn check_not_paused(&self) -> Result<()> {
+        require!(!self.paused, EscrowError::Paused);
+        Ok(())
+    }
+
+    // Protocol fee owed on a payment, rounded down. Charged on token B, except for
+    // collection offers where the NFT leg can't be split and token A is charged instead
+    pub fn fee_for(&self, amount: u64) -> Result<u64> {
+        let fee = (amount as u128)
+            .checked_mul(self.fee_bps as u128)
+            .ok_or(EscrowError::Overflow)?
+            / 10_000;
+        u64::try_from(fee).map_err(|_| error!(EscrowError::Overflow))
+    }
+}
@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+
+// ===== COUNTER OFFER STATE ACCOUNT =====
+// A taker's proposal to take a fixed amount of an escrow's token A for a different
+// amount of token B. The proposed token B sits in a counter vault owned by this PDA
+// until the maker accepts or rejects it, or the taker withdraws it.
+#[account]
+#[derive(InitSpace)]
+pub struct CounterOffer {
+    pub escrow: Pubkey, // The escrow this counter-offer responds to
+    pub maker: Pubkey,  // Maker of that escrow - the only one who can accept or reject
+    pub taker: Pubkey,  // The taker proposing the counter-offer
+    pub mint_b: Pubkey, // Token B mint, copied so the counter can be withdrawn after the escrow closes
+    pub amount_a: u64,  // Amount of token A the taker asks for, released exactly on accept
+    pub amount_b: u64,  // Amount of token B the taker offers for that token A
+    pub bump: u8,       // Bump seed for PDA - needed for signing counter vault transfers
+}
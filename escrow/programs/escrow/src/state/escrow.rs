@@ -0,0 +1,96 @@
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
+
+use crate::{error::EscrowError, state::Arbitration};
+
+// ===== ESCROW STATE ACCOUNT =====
+// This struct defines the data stored in the escrow account
+// It contains all the information needed to execute the trade
+#[account]
+#[derive(InitSpace)]
+pub struct Escrow {
+    pub seed: u64,                        // Random seed used for PDA derivation
+    pub maker: Pubkey,                    // The public key of the escrow creator
+    pub maker_recipient: Pubkey,          // Wallet that receives token B, the maker unless set otherwise
+    pub mint_a: Pubkey,                   // Token A mint (what maker is offering)
+    pub mint_b: Pubkey,                   // Token B mint (what maker wants in return)
+    pub collection: bool,                 // If set, mint_b is a collection and any verified NFT from it fills the offer
+    pub receive: u64,                     // Amount of Token B expected from the taker
+    pub remaining: u64,                   // Amount of Token B still to be filled by takers
+    pub filled: u64,                      // Amount of Token B already paid to the maker
+    pub expires_at: i64,                  // Unix timestamp after which the offer can no longer be taken
+    pub taker: Option<Pubkey>,            // If set, the only wallet allowed to take the offer
+    pub allowlist: Option<[u8; 32]>,      // If set, Merkle root of the wallets allowed to take the offer
+    pub arbitration: Option<Arbitration>, // If set, third party who settles disputes with the named taker
+    pub book_page: u32,                   // Offer book page for the mint pair that lists this escrow
+    pub bump: u8,                         // Bump seed for PDA - needed for singing during take
+}
+
+impl Escrow {
+    // Rejects takers the maker did not allow when making the offer
+    // - taker: The wallet signing the take
+    // - proof: Merkle proof of the taker's membership in the allowlist (empty when unused)
+    pub fn check_taker(&self, taker: &Pubkey, proof: &[[u8; 32]]) -> Result<()> {
+        if let Some(allowed) = self.taker {
+            require_keys_eq!(allowed, *taker, EscrowError::UnauthorizedTaker);
+        }
+
+        if let Some(root) = self.allowlist {
+            // Leaves are sha256(taker), pairs are hashed in sorted order so the proof needs no path bits
+            let mut node = hashv(&[taker.as_ref()]).to_bytes();
+            for sibling in proof {
+                node = if node <= *sibling {
+                    hashv(&[&node, sibling]).to_bytes()
+                } else {
+                    hashv(&[sibling, &node]).to_bytes()
+                };
+            }
+            require!(node == root, EscrowError::UnauthorizedTaker);
+        }
+
+        Ok(())
+    }
+
+    // Token A bought by a fill of `amount_b`, given what is left in the vault
+    // The vault always holds the token A backing the remaining token B, so the rate is
+    // vault_amount / remaining. Rounding down keeps the rate from drifting against the maker,
+    // and the final fill sweeps whatever is left in the vault.
+    pub fn amount_a_for(&self, vault_amount: u64, amount_b: u64) -> Result<u64> {
+        if amount_b == self.remaining {
+            return Ok(vault_amount);
+        }
+
+        let amount_a = (vault_amount as u128)
+            .checked_mul(amount_b as u128)
+            .ok_or(EscrowError::Overflow)?
+            / self.remaining as u128;
+
+        u64::try_from(amount_a).map_err(|_| error!(EscrowError::Overflow))
+    }
+
+    // Rejects token B fills of an offer that asks for an NFT from a collection
+    pub fn check_not_collection(&self) -> Result<()> {
+        require!(!self.collection, EscrowError::CollectionOffer);
+        Ok(())
+    }
+
+    // Rejects anything but the arbiter's ruling while a dispute is open
+    pub fn check_not_disputed(&self) -> Result<()> {
+        require!(
+            !self.arbitration.as_ref().is_some_and(|a| a.disputed),
+            EscrowError::Disputed
+        );
+        Ok(())
+    }
+
+    // Rejects refunds while a dispute is open, unless the arbiter let it time out
+    pub fn check_refundable(&self, now: i64) -> Result<()> {
+        require!(
+            !self
+                .arbitration
+                .as_ref()
+                .is_some_and(|a| a.blocks_refund(now)),
+            EscrowError::Disputed
+        );
+        Ok(())
+    }
+}
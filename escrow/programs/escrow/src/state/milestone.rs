@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::MAX_MILESTONES, error::EscrowError, state::Arbitration};
+
+// ===== MILESTONE ESCROW STATE ACCOUNT =====
+// Escrow for a service contract paid out in stages. The payer funds every milestone up
+// front into a vault owned by this PDA and releases each tranche to the payee as the
+// work is approved. Whatever is unreleased goes back to the payer once both parties
+// sign off on a refund, or once the deadline has passed.
+#[account]
+#[derive(InitSpace)]
+pub struct MilestoneEscrow {
+    pub seed: u64,                        // Random seed used for PDA derivation
+    pub payer: Pubkey,                    // Funds the escrow and approves each milestone
+    pub payee: Pubkey,                    // Receives each tranche as it is released
+    pub mint: Pubkey,                     // Token the contract is paid in
+    #[max_len(MAX_MILESTONES)]
+    pub milestones: Vec<Milestone>,       // Tranches in the order they are expected to be delivered
+    pub deadline: i64,                    // After this the payer can refund unreleased tranches alone
+    pub payer_approved_refund: bool,      // Payer signed off on refunding the remainder
+    pub payee_approved_refund: bool,      // Payee signed off on refunding the remainder
+    pub arbitration: Option<Arbitration>, // If set, third party who settles disputes between payer and payee
+    pub bump: u8,                         // Bump seed for PDA - needed for signing vault transfers
+}
+
+impl MilestoneEscrow {
+    // Both parties agreed to unwind the contract
+    pub fn refund_approved(&self) -> bool {
+        self.payer_approved_refund && self.payee_approved_refund
+    }
+
+    // Rejects anything but the arbiter's ruling while a dispute is open
+    pub fn check_not_disputed(&self) -> Result<()> {
+        require!(
+            !self.arbitration.as_ref().is_some_and(|a| a.disputed),
+            EscrowError::Disputed
+        );
+        Ok(())
+    }
+
+    // Rejects refunds while a dispute is open, unless the arbiter let it time out
+    pub fn check_refundable(&self, now: i64) -> Result<()> {
+        require!(
+            !self
+                .arbitration
+                .as_ref()
+                .is_some_and(|a| a.blocks_refund(now)),
+            EscrowError::Disputed
+        );
+        Ok(())
+    }
+}
+
+// One tranche of a milestone escrow
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Milestone {
+    pub amount: u64,                // Amount of the token released for this milestone
+    pub description_hash: [u8; 32], // Hash of the off-chain description of the deliverable
+    pub released: bool,             // Whether the payer has already released this tranche
+}
+
+// Terms of one milestone as passed to make_milestones
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MilestoneTerms {
+    pub amount: u64,
+    pub description_hash: [u8; 32],
+}
@@ -0,0 +1,18 @@
+pub mod escrow;
+pub use escrow::*;
+pub mod bundle;
+pub use bundle::*;
+pub mod counter_offer;
+pub use counter_offer::*;
+pub mod config;
+pub use config::*;
+pub mod order;
+pub use order::*;
+pub mod milestone;
+pub use milestone::*;
+pub mod arbitration;
+pub use arbitration::*;
+pub mod vesting;
+pub use vesting::*;
+pub mod offer_book;
+pub use offer_book::*;
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::OFFERS_PER_PAGE, error::EscrowError};
+
+// ===== OFFER BOOK STATE ACCOUNT =====
+// One page of the index of open escrows for a (mint_a, mint_b) pair, so takers can list
+// every offer for a pair by fetching pages 0, 1, 2... until one is missing.
+// `make` adds the escrow to the page the maker picks, and the entry is removed when the
+// escrow is closed by a full take, a refund or a settlement.
+#[account]
+#[derive(InitSpace)]
+pub struct OfferBook {
+    pub mint_a: Pubkey, // Token A mint of every offer on this page
+    pub mint_b: Pubkey, // Token B mint of every offer on this page
+    pub page: u32,      // Index of this page for the pair
+    // Open escrows, in no particular order
+    #[max_len(OFFERS_PER_PAGE)]
+    pub offers: Vec<Pubkey>,
+    pub bump: u8,       // Bump seed for PDA
+}
+
+impl OfferBook {
+    // Lists a new escrow, failing if the page is full so the client can pick another one
+    pub fn insert(&mut self, escrow: Pubkey) -> Result<()> {
+        require!(
+            self.offers.len() < OFFERS_PER_PAGE as usize,
+            EscrowError::OfferBookFull
+        );
+        self.offers.push(escrow);
+        Ok(())
+    }
+
+    // Unlists a closed escrow
+    pub fn remove(&mut self, escrow: &Pubkey) -> Result<()> {
+        let index = self
+            .offers
+            .iter()
+            .position(|offer| offer == escrow)
+            .ok_or(EscrowError::OfferNotInBook)?;
+        self.offers.swap_remove(index);
+        Ok(())
+    }
+}
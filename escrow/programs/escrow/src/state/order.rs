@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::NONCES_PER_PAGE, error::EscrowError};
+
+// ===== SIGNED ORDER =====
+// An offer the maker signs off-chain with their wallet's ed25519 key instead of creating
+// an Escrow PDA and vault. The maker approves the order authority PDA as delegate on their
+// token A account, and a taker settles the order directly from the maker's wallet.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SignedOrder {
+    pub maker: Pubkey,   // The wallet that signed the order
+    pub mint_a: Pubkey,  // Token A mint (what maker is offering)
+    pub mint_b: Pubkey,  // Token B mint (what maker wants in return)
+    pub amount_a: u64,   // Amount of token A the taker receives
+    pub amount_b: u64,   // Amount of token B the taker pays
+    pub nonce: u64,      // Unique per maker, marked used on fill or cancel to prevent replays
+    pub expires_at: i64, // Unix timestamp after which the order can no longer be filled
+}
+
+impl SignedOrder {
+    // The exact bytes the maker signs: the program id followed by the borsh-encoded order,
+    // so a signature can't be replayed against another program
+    pub fn message(&self) -> Result<Vec<u8>> {
+        let mut message = crate::ID.to_bytes().to_vec();
+        self.serialize(&mut message)?;
+        Ok(message)
+    }
+}
+
+// ===== ORDER NONCES STATE ACCOUNT =====
+// Bitmap of a maker's used or cancelled order nonces.
+// Each page covers NONCES_PER_PAGE nonces: page = nonce / NONCES_PER_PAGE.
+#[account]
+#[derive(InitSpace)]
+pub struct OrderNonces {
+    pub maker: Pubkey,     // The maker these nonces belong to
+    pub page: u64,         // Index of this page of nonces
+    pub bitmap: [u8; 128], // One bit per nonce in the page, set once used or cancelled
+    pub bump: u8,          // Bump seed for PDA
+}
+
+impl OrderNonces {
+    // Marks a nonce as used, failing if it already was
+    pub fn consume(&mut self, nonce: u64) -> Result<()> {
+        let bit = (nonce % NONCES_PER_PAGE) as usize;
+        let (byte, mask) = (bit / 8, 1u8 << (bit % 8));
+
+        require!(self.bitmap[byte] & mask == 0, EscrowError::OrderNonceUsed);
+        self.bitmap[byte] |= mask;
+        Ok(())
+    }
+}
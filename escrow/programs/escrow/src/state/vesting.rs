@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+
+use crate::error::EscrowError;
+
+// ===== VESTING STATE ACCOUNT =====
+// Escrow that releases tokens to a beneficiary linearly between `start` and `end`,
+// with nothing claimable before the cliff. An optional revoker can stop the schedule,
+// returning the unvested part to the funder.
+#[account]
+#[derive(InitSpace)]
+pub struct Vesting {
+    pub seed: u64,                 // Random seed used for PDA derivation
+    pub funder: Pubkey,            // Deposited the tokens and gets the unvested part back on revoke
+    pub beneficiary: Pubkey,       // Can claim whatever has vested
+    pub mint: Pubkey,              // Token being vested
+    pub total: u64,                // Amount vested by the end of the schedule
+    pub claimed: u64,              // Amount the beneficiary has already claimed
+    pub schedule: VestingSchedule, // When the tokens vest
+    pub revoker: Option<Pubkey>,   // If set, can claw back the unvested part
+    pub bump: u8,                  // Bump seed for PDA - needed for signing vault transfers
+}
+
+impl Vesting {
+    // Amount vested at `now`, claimed or not
+    pub fn vested(&self, now: i64) -> Result<u64> {
+        let VestingSchedule { start, cliff, end } = self.schedule;
+        if now < cliff {
+            return Ok(0);
+        }
+        if now >= end {
+            return Ok(self.total);
+        }
+
+        let elapsed = now.checked_sub(start).ok_or(EscrowError::Overflow)?;
+        let duration = end.checked_sub(start).ok_or(EscrowError::Overflow)?;
+        let vested = (self.total as u128)
+            .checked_mul(elapsed as u128)
+            .ok_or(EscrowError::Overflow)?
+            / duration as u128;
+        Ok(vested as u64)
+    }
+}
+
+// Unix timestamps of a linear vesting schedule
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct VestingSchedule {
+    pub start: i64, // Vesting starts accruing here
+    pub cliff: i64, // Nothing can be claimed before this
+    pub end: i64,   // Everything has vested from here on
+}
+
+impl VestingSchedule {
+    // The schedule's length must fit in an i64 so vested() can divide by it
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.start <= self.cliff
+                && self.cliff <= self.end
+                && self.start < self.end
+                && self.end.checked_sub(self.start).is_some(),
+            EscrowError::InvalidSchedule
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+// Offers restricted to a named taker or a Merkle allowlist of takers.
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_lang::solana_program::hash::hashv;
+use anchor_spl::token::spl_token;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+use common::*;
+
+#[tokio::test]
+async fn take_is_limited_to_the_named_taker() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.named_taker = Some(Pubkey::new_unique());
+    s.make(1).await.unwrap();
+    s.named_taker = Some(s.taker.pubkey());
+    s.make(2).await.unwrap();
+
+    let err = s.take(1, 1).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::UnauthorizedTaker.into())
+    );
+
+    s.take(2, 2).await.unwrap();
+    let taker_a = s.ata(s.mint_a, &s.taker.pubkey());
+    assert_eq!(s.token_balance(&taker_a).await, DEPOSIT);
+}
+
+#[tokio::test]
+async fn take_checks_the_allowlist_proof() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    let taker_leaf = hashv(&[s.taker.pubkey().as_ref()]).to_bytes();
+    let other_leaf = hashv(&[Pubkey::new_unique().as_ref()]).to_bytes();
+    let (low, high) = (taker_leaf.min(other_leaf), taker_leaf.max(other_leaf));
+    s.allowlist = Some(hashv(&[&low, &high]).to_bytes());
+    s.make(1).await.unwrap();
+
+    let err = s.take_with_proof(1, vec![]).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::UnauthorizedTaker.into())
+    );
+
+    s.take_with_proof(1, vec![other_leaf]).await.unwrap();
+    let taker_a = s.ata(s.mint_a, &s.taker.pubkey());
+    assert_eq!(s.token_balance(&taker_a).await, DEPOSIT);
+}
@@ -0,0 +1,125 @@
+// Multi-asset bundle offers: make_bundle, take_bundle and refund_bundle.
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_spl::{token::spl_token, token_2022::spl_token_2022};
+use solana_sdk::signature::Signer;
+
+use common::*;
+
+#[tokio::test]
+async fn make_bundle_checks_requested_mints() {
+    let mut s = Setup::new(spl_token_2022::ID, RECEIVE, None).await;
+    let leg = |mint| escrow::Leg { mint, amount: 1 };
+    let offered = vec![leg(s.mint_a)];
+
+    // A permanent delegate could pull the tokens back out of the maker's account
+    let delegated = s.create_mint_with(None, true).await;
+    let err = s
+        .make_bundle(1, offered.clone(), vec![leg(s.mint_b), leg(delegated)])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::UnsupportedMintExtension.into())
+    );
+
+    // A bundle moves every leg through one token program, so SPL Token mints are refused
+    // when the bundle is made under Token-2022
+    s.token_program = spl_token::ID;
+    let legacy = s.create_mint(None).await;
+    s.token_program = spl_token_2022::ID;
+    let err = s
+        .make_bundle(1, offered.clone(), vec![leg(s.mint_b), leg(legacy)])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::InvalidBundleAccounts.into())
+    );
+
+    s.make_bundle(1, offered, vec![leg(s.mint_b)])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn take_bundle_swaps_every_leg() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    let (maker, taker) = (s.maker.pubkey(), s.taker.pubkey());
+    let (mint_a, mint_b) = (s.mint_a, s.mint_b);
+    let mint_c = s.create_mint(None).await;
+    s.mint_to(mint_c, &maker, 300).await;
+    let mint_d = s.create_mint(None).await;
+    s.mint_to(mint_d, &taker, 200).await;
+
+    let leg = |mint, amount| escrow::Leg { mint, amount };
+    let offered = vec![leg(mint_a, DEPOSIT), leg(mint_c, 300)];
+    let requested = vec![leg(mint_b, RECEIVE), leg(mint_d, 200)];
+    s.make_bundle(1, offered, requested).await.unwrap();
+    assert_eq!(s.token_balance(&s.ata(mint_a, &s.bundle(1))).await, DEPOSIT);
+    assert_eq!(s.token_balance(&s.ata(mint_c, &s.bundle(1))).await, 300);
+
+    s.take_bundle(1, &[mint_a, mint_c], &[mint_b, mint_d])
+        .await
+        .unwrap();
+
+    assert_eq!(s.token_balance(&s.ata(mint_a, &taker)).await, DEPOSIT);
+    assert_eq!(s.token_balance(&s.ata(mint_c, &taker)).await, 300);
+    assert_eq!(s.token_balance(&s.ata(mint_b, &maker)).await, RECEIVE);
+    assert_eq!(s.token_balance(&s.ata(mint_d, &maker)).await, 200);
+    assert_eq!(s.token_balance(&s.ata(mint_b, &taker)).await, 0);
+    assert_eq!(s.token_balance(&s.ata(mint_d, &taker)).await, 0);
+    assert_eq!(s.token_balance(&s.ata(mint_a, &maker)).await, DEPOSIT);
+    assert_eq!(s.token_balance(&s.ata(mint_c, &maker)).await, 0);
+    assert!(!s.exists(&s.ata(mint_a, &s.bundle(1))).await);
+    assert!(!s.exists(&s.ata(mint_c, &s.bundle(1))).await);
+    assert!(!s.exists(&s.bundle(1)).await);
+}
+
+#[tokio::test]
+async fn refund_bundle_returns_every_deposit() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    let maker = s.maker.pubkey();
+    let (mint_a, mint_b) = (s.mint_a, s.mint_b);
+    let mint_c = s.create_mint(None).await;
+    s.mint_to(mint_c, &maker, 300).await;
+
+    let leg = |mint, amount| escrow::Leg { mint, amount };
+    let offered = vec![leg(mint_a, DEPOSIT), leg(mint_c, 300)];
+    s.make_bundle(1, offered, vec![leg(mint_b, RECEIVE)])
+        .await
+        .unwrap();
+    assert_eq!(s.token_balance(&s.ata(mint_c, &maker)).await, 0);
+
+    let signer = s.maker.insecure_clone();
+    s.refund_bundle(&signer, 1, &[mint_a, mint_c])
+        .await
+        .unwrap();
+
+    assert_eq!(s.token_balance(&s.ata(mint_a, &maker)).await, DEPOSIT * 2);
+    assert_eq!(s.token_balance(&s.ata(mint_c, &maker)).await, 300);
+    assert!(!s.exists(&s.ata(mint_a, &s.bundle(1))).await);
+    assert!(!s.exists(&s.ata(mint_c, &s.bundle(1))).await);
+    assert!(!s.exists(&s.bundle(1)).await);
+}
+
+#[tokio::test]
+async fn refund_bundle_by_non_maker_fails() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    let (mint_a, mint_b) = (s.mint_a, s.mint_b);
+    let leg = |mint, amount| escrow::Leg { mint, amount };
+    s.make_bundle(1, vec![leg(mint_a, DEPOSIT)], vec![leg(mint_b, RECEIVE)])
+        .await
+        .unwrap();
+
+    let outsider = s.taker.insecure_clone();
+    let err = s.refund_bundle(&outsider, 1, &[mint_a]).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(anchor_lang::error::ErrorCode::ConstraintSeeds as u32)
+    );
+    assert_eq!(s.token_balance(&s.ata(mint_a, &s.bundle(1))).await, DEPOSIT);
+    assert!(s.exists(&s.bundle(1)).await);
+}
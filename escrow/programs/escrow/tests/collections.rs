@@ -0,0 +1,78 @@
+// Collection offers filled with any verified NFT of the collection through take_collection.
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_spl::{token::spl_token, token_2022::spl_token_2022};
+use solana_sdk::signature::Signer;
+
+use common::*;
+
+#[tokio::test]
+async fn take_collection_with_verified_nft() {
+    let mut s = Setup::new(spl_token::ID, 0, None).await;
+    s.make_offer(1, 1, true).await.unwrap();
+    let nft_mint = s.give_collection_nft(true).await;
+
+    // The collection mint itself can't be paid as token B
+    let err = s.take(1, 1).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::CollectionOffer.into())
+    );
+
+    s.take_collection(1, nft_mint).await.unwrap();
+
+    let (maker, taker) = (s.maker.pubkey(), s.taker.pubkey());
+    assert_eq!(s.token_balance(&s.ata(s.mint_a, &taker)).await, DEPOSIT);
+    assert_eq!(s.token_balance(&s.ata(nft_mint, &maker)).await, 1);
+    assert!(!s.exists(&s.vault(1)).await);
+    assert!(!s.exists(&s.escrow(1)).await);
+    assert!(s.listed_offers().await.is_empty());
+}
+
+#[tokio::test]
+async fn take_collection_takes_the_protocol_fee_in_token_a() {
+    let mut s = Setup::new(spl_token::ID, 0, None).await;
+    s.set_fee(100).await.unwrap();
+    s.make_offer(1, 1, true).await.unwrap();
+    let nft_mint = s.give_collection_nft(true).await;
+
+    s.take_collection(1, nft_mint).await.unwrap();
+
+    let fee = DEPOSIT / 100;
+    let taker = s.taker.pubkey();
+    assert_eq!(
+        s.token_balance(&s.ata(s.mint_a, &taker)).await,
+        DEPOSIT - fee
+    );
+    assert_eq!(s.token_balance(&s.ata(s.mint_a, &s.payer())).await, fee);
+}
+
+#[tokio::test]
+async fn take_collection_with_unverified_nft_fails() {
+    let mut s = Setup::new(spl_token::ID, 0, None).await;
+    s.make_offer(1, 1, true).await.unwrap();
+    let nft_mint = s.give_collection_nft(false).await;
+
+    let err = s.take_collection(1, nft_mint).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::NotInCollection.into())
+    );
+    assert_eq!(s.token_balance(&s.vault(1)).await, DEPOSIT);
+}
+
+#[tokio::test]
+async fn take_collection_rejects_nfts_with_a_permanent_delegate() {
+    let mut s = Setup::new(spl_token_2022::ID, 0, None).await;
+    s.make_offer(1, 1, true).await.unwrap();
+    let nft_mint = s.give_collection_nft_with(true, true).await;
+
+    let err = s.take_collection(1, nft_mint).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::UnsupportedMintExtension.into())
+    );
+    assert_eq!(s.token_balance(&s.vault(1)).await, DEPOSIT);
+}
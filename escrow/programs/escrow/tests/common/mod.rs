@@ -0,0 +1,1436 @@
+// Shared setup for the integration tests: a Setup with two mints, a maker and a taker,
+// and a helper that builds and sends each escrow instruction. Not every test file uses
+// every helper.
+#![allow(dead_code)]
+
+use anchor_lang::{system_program, AccountDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::{
+    associated_token::{
+        get_associated_token_address_with_program_id,
+        spl_associated_token_account::{self, instruction::create_associated_token_account},
+    },
+    metadata::mpl_token_metadata,
+    token::spl_token::native_mint,
+    token_2022::spl_token_2022::{
+        self,
+        extension::{transfer_fee::instruction::initialize_transfer_fee_config, ExtensionType},
+    },
+};
+use escrow::{Escrow, OfferBook};
+use solana_ed25519_program::new_ed25519_instruction_with_signature;
+use solana_program_test::{BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::{AccountSharedData, WritableAccount},
+    bpf_loader,
+    bpf_loader_upgradeable::get_program_data_address,
+    clock::Clock,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::{Transaction, TransactionError},
+};
+
+pub const DEPOSIT: u64 = 1_000;
+pub const RECEIVE: u64 = 500;
+
+pub struct Setup {
+    pub ctx: ProgramTestContext,
+    pub token_program: Pubkey,
+    pub maker: Keypair,
+    pub taker: Keypair,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    // Wallet new offers route token B to, the maker when unset
+    pub maker_recipient: Option<Pubkey>,
+    // Merkle root of the takers allowed on new offers, open to anyone when unset
+    pub allowlist: Option<[u8; 32]>,
+    // Only wallet allowed to take new offers, anyone when unset
+    pub named_taker: Option<Pubkey>,
+    // Arbiter of new offers, which also needs a named taker
+    pub arbitration: Option<escrow::ArbiterTerms>,
+    // Log messages of the last transaction that went through `send`
+    pub logs: Vec<String>,
+}
+
+impl Setup {
+    // Two mints under `token_program`, a maker holding 2 x DEPOSIT of token A, a taker holding
+    // `taker_b` of token B and a zero-fee config. `fee_bps_a` adds a Token-2022 transfer fee to mint A.
+    pub async fn new(token_program: Pubkey, taker_b: u64, fee_bps_a: Option<u16>) -> Self {
+        let ctx = start().await;
+
+        let mut setup = Setup {
+            ctx,
+            token_program,
+            maker: Keypair::new(),
+            taker: Keypair::new(),
+            mint_a: Pubkey::default(),
+            mint_b: Pubkey::default(),
+            maker_recipient: None,
+            allowlist: None,
+            named_taker: None,
+            arbitration: None,
+            logs: vec![],
+        };
+
+        for wallet in [setup.maker.pubkey(), setup.taker.pubkey()] {
+            setup.airdrop(&wallet, 1_000_000_000).await;
+        }
+        setup.mint_a = setup.create_mint(fee_bps_a).await;
+        setup.mint_b = setup.create_mint(None).await;
+
+        let maker = setup.maker.pubkey();
+        let taker = setup.taker.pubkey();
+        setup.mint_to(setup.mint_a, &maker, DEPOSIT * 2).await;
+        setup.mint_to(setup.mint_b, &taker, taker_b).await;
+        setup.create_ata(setup.mint_b, &maker).await;
+        // The fee recipient's account is made up front so takes don't charge the taker its rent
+        let payer = setup.payer();
+        setup.create_ata(setup.mint_b, &payer).await;
+
+        let initialize_config = initialize_config_ix(setup.payer(), 0);
+        setup.send(&[initialize_config], &[]).await.unwrap();
+
+        setup
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.ctx.payer.pubkey()
+    }
+
+    // Sends the instructions with the context payer paying fees, so balance checks on
+    // the maker and taker only see what the program moved. Keeps the logs for event checks.
+    pub async fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let blockhash = self.ctx.get_new_latest_blockhash().await.unwrap();
+        let mut all_signers = vec![&self.ctx.payer];
+        all_signers.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.ctx.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        let result = self
+            .ctx
+            .banks_client
+            .process_transaction_with_metadata(tx)
+            .await?;
+        self.logs = result
+            .metadata
+            .map(|metadata| metadata.log_messages)
+            .unwrap_or_default();
+        Ok(result.result?)
+    }
+
+    pub async fn airdrop(&mut self, to: &Pubkey, lamports: u64) {
+        let ix = system_instruction::transfer(&self.payer(), to, lamports);
+        self.send(&[ix], &[]).await.unwrap();
+    }
+
+    pub async fn create_mint(&mut self, transfer_fee_bps: Option<u16>) -> Pubkey {
+        self.create_mint_with(transfer_fee_bps, false).await
+    }
+
+    // - transfer_fee_bps: Adds a Token-2022 transfer fee
+    // - permanent_delegate: Makes the payer a Token-2022 permanent delegate of the mint
+    pub async fn create_mint_with(
+        &mut self,
+        transfer_fee_bps: Option<u16>,
+        permanent_delegate: bool,
+    ) -> Pubkey {
+        let mint = Keypair::new();
+        let payer = self.payer();
+        let mut extensions = vec![];
+        if transfer_fee_bps.is_some() {
+            extensions.push(ExtensionType::TransferFeeConfig);
+        }
+        if permanent_delegate {
+            extensions.push(ExtensionType::PermanentDelegate);
+        }
+        let space =
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&extensions)
+                .unwrap();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+
+        let mut ixs = vec![system_instruction::create_account(
+            &payer,
+            &mint.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &self.token_program,
+        )];
+        if let Some(bps) = transfer_fee_bps {
+            ixs.push(
+                initialize_transfer_fee_config(
+                    &self.token_program,
+                    &mint.pubkey(),
+                    Some(&payer),
+                    Some(&payer),
+                    bps,
+                    u64::MAX,
+                )
+                .unwrap(),
+            );
+        }
+        if permanent_delegate {
+            ixs.push(
+                spl_token_2022::instruction::initialize_permanent_delegate(
+                    &self.token_program,
+                    &mint.pubkey(),
+                    &payer,
+                )
+                .unwrap(),
+            );
+        }
+        ixs.push(
+            spl_token_2022::instruction::initialize_mint2(
+                &self.token_program,
+                &mint.pubkey(),
+                &payer,
+                None,
+                6,
+            )
+            .unwrap(),
+        );
+
+        self.send(&ixs, &[&mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    pub async fn create_ata(&mut self, mint: Pubkey, owner: &Pubkey) -> Pubkey {
+        let ix = create_associated_token_account(&self.payer(), owner, &mint, &self.token_program);
+        self.send(&[ix], &[]).await.unwrap();
+        self.ata(mint, owner)
+    }
+
+    pub async fn mint_to(&mut self, mint: Pubkey, owner: &Pubkey, amount: u64) {
+        let ata = self.create_ata(mint, owner).await;
+        let ix = spl_token_2022::instruction::mint_to(
+            &self.token_program,
+            &mint,
+            &ata,
+            &self.payer(),
+            &[],
+            amount,
+        )
+        .unwrap();
+        self.send(&[ix], &[]).await.unwrap();
+    }
+
+    pub fn ata(&self, mint: Pubkey, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, &mint, &self.token_program)
+    }
+
+    // Offers native SOL, which the maker deposits from their wallet, instead of mint A
+    pub fn offer_native_sol(&mut self) {
+        self.mint_a = native_mint::ID;
+    }
+
+    pub fn native_a(&self) -> bool {
+        self.mint_a == native_mint::ID
+    }
+
+    pub fn escrow(&self, seed: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"escrow", self.maker.pubkey().as_ref(), &seed.to_le_bytes()],
+            &escrow::ID,
+        )
+        .0
+    }
+
+    // Temporary wSOL account native SOL is unwrapped through
+    pub fn unwrap(&self, seed: u64) -> Pubkey {
+        Pubkey::find_program_address(&[b"unwrap", self.escrow(seed).as_ref()], &escrow::ID).0
+    }
+
+    pub fn vault(&self, seed: u64) -> Pubkey {
+        self.ata(self.mint_a, &self.escrow(seed))
+    }
+
+    // First offer book page for the pair, which every test lists its escrows on
+    pub fn book(&self) -> Pubkey {
+        self.book_page(0)
+    }
+
+    pub fn book_page(&self, page: u32) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"book",
+                self.mint_a.as_ref(),
+                self.mint_b.as_ref(),
+                &page.to_le_bytes(),
+            ],
+            &escrow::ID,
+        )
+        .0
+    }
+
+    pub async fn listed_offers(&mut self) -> Vec<Pubkey> {
+        let account = self
+            .ctx
+            .banks_client
+            .get_account(self.book())
+            .await
+            .unwrap()
+            .unwrap();
+        let book = OfferBook::try_deserialize(&mut account.data.as_slice()).unwrap();
+        book.offers
+    }
+
+    pub async fn escrow_state(&mut self, seed: u64) -> Escrow {
+        let account = self
+            .ctx
+            .banks_client
+            .get_account(self.escrow(seed))
+            .await
+            .unwrap()
+            .unwrap();
+        Escrow::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub async fn now(&mut self) -> i64 {
+        let clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp
+    }
+
+    pub async fn lamports(&mut self, address: &Pubkey) -> u64 {
+        self.ctx.banks_client.get_balance(*address).await.unwrap()
+    }
+
+    pub async fn exists(&mut self, address: &Pubkey) -> bool {
+        self.ctx
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .is_some()
+    }
+
+    // The amount sits at the same offset for SPL Token and Token-2022 accounts
+    pub async fn token_balance(&mut self, address: &Pubkey) -> u64 {
+        let account = self
+            .ctx
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .unwrap();
+        u64::from_le_bytes(account.data[64..72].try_into().unwrap())
+    }
+
+    pub async fn make(&mut self, seed: u64) -> Result<(), BanksClientError> {
+        self.make_offer(seed, RECEIVE, false).await
+    }
+
+    // - collection: Ask for any verified NFT of the collection mint_b instead of token B
+    pub async fn make_offer(
+        &mut self,
+        seed: u64,
+        receive: u64,
+        collection: bool,
+    ) -> Result<(), BanksClientError> {
+        self.make_on_page(seed, receive, collection, 0, None).await
+    }
+
+    // - book_page: Offer book page to list the escrow on
+    // - prev_book: Page passed as the one before it
+    pub async fn make_on_page(
+        &mut self,
+        seed: u64,
+        receive: u64,
+        collection: bool,
+        book_page: u32,
+        prev_book: Option<Pubkey>,
+    ) -> Result<(), BanksClientError> {
+        let maker = self.maker.pubkey();
+        let expires_at = self.now().await + 3_600;
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::Make {
+                maker,
+                mint_a: self.mint_a,
+                mint_b: self.mint_b,
+                maker_ata_a: (!self.native_a()).then(|| self.ata(self.mint_a, &maker)),
+                escrow: self.escrow(seed),
+                vault: self.vault(seed),
+                book: self.book_page(book_page),
+                prev_book,
+                config: config_pda(),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::Make {
+                seed,
+                book_page,
+                deposit: DEPOSIT,
+                receive,
+                collection,
+                expires_at,
+                taker: self.named_taker,
+                allowlist: self.allowlist,
+                arbitration: self.arbitration.clone(),
+                maker_recipient: self.maker_recipient,
+            }
+            .data(),
+        };
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &[&maker]).await
+    }
+
+    // - seed: Seed passed as the instruction argument
+    // - escrow_seed: Seed of the escrow actually passed in the accounts
+    pub async fn take(&mut self, seed: u64, escrow_seed: u64) -> Result<(), BanksClientError> {
+        self.take_with_limits(seed, escrow_seed, u64::MAX, 0).await
+    }
+
+    pub async fn take_with_limits(
+        &mut self,
+        seed: u64,
+        escrow_seed: u64,
+        max_amount_b: u64,
+        min_amount_a: u64,
+    ) -> Result<(), BanksClientError> {
+        let data = escrow::instruction::Take {
+            _seed: seed,
+            max_amount_b,
+            min_amount_a,
+            proof: vec![],
+        };
+        self.send_take(escrow_seed, data.data()).await
+    }
+
+    // Takes the escrow at `seed` with an allowlist proof
+    pub async fn take_with_proof(
+        &mut self,
+        seed: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<(), BanksClientError> {
+        let data = escrow::instruction::Take {
+            _seed: seed,
+            max_amount_b: u64::MAX,
+            min_amount_a: 0,
+            proof,
+        };
+        self.send_take(seed, data.data()).await
+    }
+
+    pub async fn take_partial(
+        &mut self,
+        seed: u64,
+        amount_b: u64,
+        min_amount_a: u64,
+    ) -> Result<(), BanksClientError> {
+        let data = escrow::instruction::TakePartial {
+            _seed: seed,
+            amount_b,
+            min_amount_a,
+            proof: vec![],
+        };
+        self.send_take(seed, data.data()).await
+    }
+
+    // Sends take or take_partial for the escrow at `escrow_seed`, signed by the taker
+    pub async fn send_take(
+        &mut self,
+        escrow_seed: u64,
+        data: Vec<u8>,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: self.take_accounts(escrow_seed),
+            data,
+        };
+        let taker = self.taker.insecure_clone();
+        self.send(&[ix], &[&taker]).await
+    }
+
+    pub fn take_accounts(&self, escrow_seed: u64) -> Vec<AccountMeta> {
+        let maker = self.maker.pubkey();
+        let taker = self.taker.pubkey();
+        let maker_recipient = self.maker_recipient.unwrap_or(maker);
+        escrow::accounts::Take {
+            taker,
+            maker,
+            maker_recipient,
+            recipient: None,
+            mint_a: self.mint_a,
+            mint_b: self.mint_b,
+            taker_ata_a: (!self.native_a()).then(|| self.ata(self.mint_a, &taker)),
+            recipient_ata_a: None,
+            unwrap_a: self.native_a().then(|| self.unwrap(escrow_seed)),
+            taker_ata_b: Some(self.ata(self.mint_b, &taker)),
+            maker_ata_b: Some(self.ata(self.mint_b, &maker_recipient)),
+            escrow: self.escrow(escrow_seed),
+            vault: self.vault(escrow_seed),
+            book: self.book(),
+            config: config_pda(),
+            fee_recipient: self.payer(),
+            fee_ata_b: Some(self.ata(self.mint_b, &self.payer())),
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: self.token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None)
+    }
+
+    // Gives the taker an NFT of `nft_mint` whose metadata names mint_b as its collection.
+    // The metadata program is not loaded, so its accounts are written directly in the
+    // layout take_collection reads, and an empty executable account stands in for it.
+    pub async fn give_collection_nft(&mut self, verified: bool) -> Pubkey {
+        self.give_collection_nft_with(verified, false).await
+    }
+
+    // - permanent_delegate: Makes the payer a Token-2022 permanent delegate of the NFT mint
+    pub async fn give_collection_nft_with(
+        &mut self,
+        verified: bool,
+        permanent_delegate: bool,
+    ) -> Pubkey {
+        let nft_mint = self.create_mint_with(None, permanent_delegate).await;
+        let taker = self.taker.pubkey();
+        self.mint_to(nft_mint, &taker, 1).await;
+
+        let mut metadata = vec![4]; // Key::MetadataV1
+        metadata.extend_from_slice(self.payer().as_ref()); // update_authority
+        metadata.extend_from_slice(nft_mint.as_ref());
+        for text in ["NFT", "NFT", ""] {
+            metadata.extend_from_slice(&(text.len() as u32).to_le_bytes());
+            metadata.extend_from_slice(text.as_bytes());
+        }
+        metadata.extend_from_slice(&0u16.to_le_bytes()); // seller_fee_basis_points
+        metadata.extend_from_slice(&[0, 0, 1, 0, 0]); // creators, sale, mutable, nonce, standard
+        metadata.extend_from_slice(&[1, verified as u8]);
+        metadata.extend_from_slice(self.mint_b.as_ref());
+        metadata.extend_from_slice(&[0, 0, 0]); // uses, details, programmable config
+
+        let mut master_edition = vec![6]; // Key::MasterEditionV2
+        master_edition.extend_from_slice(&0u64.to_le_bytes());
+        master_edition.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        self.set_metadata_account(nft_metadata(nft_mint), metadata);
+        self.set_metadata_account(nft_master_edition(nft_mint), master_edition);
+        let mut program = AccountSharedData::new(1, 0, &bpf_loader::ID);
+        program.set_executable(true);
+        self.ctx.set_account(&mpl_token_metadata::ID, &program);
+
+        nft_mint
+    }
+
+    pub fn set_metadata_account(&mut self, address: Pubkey, data: Vec<u8>) {
+        let mut account =
+            AccountSharedData::new(1_000_000_000, data.len(), &mpl_token_metadata::ID);
+        account.set_data_from_slice(&data);
+        self.ctx.set_account(&address, &account);
+    }
+
+    pub async fn take_collection(
+        &mut self,
+        seed: u64,
+        nft_mint: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let maker = self.maker.pubkey();
+        let taker = self.taker.pubkey();
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::TakeCollection {
+                taker,
+                maker,
+                maker_recipient: maker,
+                mint_a: self.mint_a,
+                nft_mint,
+                metadata: nft_metadata(nft_mint),
+                master_edition: nft_master_edition(nft_mint),
+                taker_ata_a: Some(self.ata(self.mint_a, &taker)),
+                unwrap_a: None,
+                taker_nft_ata: self.ata(nft_mint, &taker),
+                maker_nft_ata: self.ata(nft_mint, &maker),
+                escrow: self.escrow(seed),
+                vault: self.vault(seed),
+                book: self.book(),
+                config: config_pda(),
+                fee_recipient: self.payer(),
+                fee_ata_a: Some(self.ata(self.mint_a, &self.payer())),
+                metadata_program: mpl_token_metadata::ID,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::TakeCollection { proof: vec![] }.data(),
+        };
+        let taker = self.taker.insecure_clone();
+        self.send(&[ix], &[&taker]).await
+    }
+
+    // Pauses or unpauses the program, signed by the config admin (the context payer)
+    pub async fn set_paused(&mut self, paused: bool) -> Result<(), BanksClientError> {
+        let accounts = escrow::accounts::UpdateConfig {
+            admin: self.payer(),
+            config: config_pda(),
+        }
+        .to_account_metas(None);
+        let data = match paused {
+            true => escrow::instruction::Pause {}.data(),
+            false => escrow::instruction::Unpause {}.data(),
+        };
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts,
+            data,
+        };
+        self.send(&[ix], &[]).await
+    }
+
+    pub async fn set_fee(&mut self, fee_bps: u16) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::UpdateConfig {
+                admin: self.payer(),
+                config: config_pda(),
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::UpdateFee {
+                fee_bps,
+                fee_recipient: self.payer(),
+            }
+            .data(),
+        };
+        self.send(&[ix], &[]).await
+    }
+
+    // Fills the maker's escrows at `seeds` in order through take_many
+    pub async fn take_many(
+        &mut self,
+        seeds: &[u64],
+        budget_b: u64,
+        max_price: u64,
+        skip_unfillable: bool,
+        proofs: Vec<Vec<[u8; 32]>>,
+    ) -> Result<(), BanksClientError> {
+        let maker = self.maker.pubkey();
+        let taker = self.taker.pubkey();
+        let mut accounts = escrow::accounts::TakeMany {
+            taker,
+            mint_a: self.mint_a,
+            mint_b: self.mint_b,
+            taker_ata_a: self.ata(self.mint_a, &taker),
+            taker_ata_b: self.ata(self.mint_b, &taker),
+            config: config_pda(),
+            fee_recipient: self.payer(),
+            fee_ata_b: Some(self.ata(self.mint_b, &self.payer())),
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: self.token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        for &seed in seeds {
+            accounts.extend([
+                AccountMeta::new(self.escrow(seed), false),
+                AccountMeta::new(self.vault(seed), false),
+                AccountMeta::new(self.ata(self.mint_b, &maker), false),
+                AccountMeta::new(maker, false),
+                AccountMeta::new(self.book(), false),
+            ]);
+        }
+
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts,
+            data: escrow::instruction::TakeMany {
+                budget_b,
+                max_price,
+                skip_unfillable,
+                proofs,
+            }
+            .data(),
+        };
+        let taker = self.taker.insecure_clone();
+        self.send(&[ix], &[&taker]).await
+    }
+
+    // Refunds the maker's escrow at `seed`, signed by `signer`
+    pub async fn refund(&mut self, signer: &Keypair, seed: u64) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::Refund {
+                maker: signer.pubkey(),
+                mint_a: self.mint_a,
+                maker_ata_a: Some(self.ata(self.mint_a, &signer.pubkey())),
+                escrow: self.escrow(seed),
+                vault: self.vault(seed),
+                book: self.book(),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::Refund {}.data(),
+        };
+        self.send(&[ix], &[signer]).await
+    }
+
+    pub fn counter(&self, seed: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"counter",
+                self.escrow(seed).as_ref(),
+                self.taker.pubkey().as_ref(),
+            ],
+            &escrow::ID,
+        )
+        .0
+    }
+
+    // The taker counters the escrow at `seed`, asking `amount_a` of token A for `amount_b`
+    pub async fn make_counter(
+        &mut self,
+        seed: u64,
+        amount_a: u64,
+        amount_b: u64,
+    ) -> Result<(), BanksClientError> {
+        let taker = self.taker.pubkey();
+        let counter = self.counter(seed);
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::MakeCounter {
+                taker,
+                mint_b: self.mint_b,
+                taker_ata_b: Some(self.ata(self.mint_b, &taker)),
+                escrow: self.escrow(seed),
+                counter,
+                counter_vault: self.ata(self.mint_b, &counter),
+                config: config_pda(),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::MakeCounter { amount_a, amount_b }.data(),
+        };
+        let taker = self.taker.insecure_clone();
+        self.send(&[ix], &[&taker]).await
+    }
+
+    pub async fn accept_counter(&mut self, seed: u64) -> Result<(), BanksClientError> {
+        let (maker, taker) = (self.maker.pubkey(), self.taker.pubkey());
+        let maker_recipient = self.maker_recipient.unwrap_or(maker);
+        let counter = self.counter(seed);
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::AcceptCounter {
+                maker,
+                taker,
+                maker_recipient,
+                mint_a: self.mint_a,
+                mint_b: self.mint_b,
+                maker_ata_b: Some(self.ata(self.mint_b, &maker_recipient)),
+                maker_ata_a: Some(self.ata(self.mint_a, &maker)),
+                taker_ata_a: Some(self.ata(self.mint_a, &taker)),
+                escrow: self.escrow(seed),
+                vault: self.vault(seed),
+                book: self.book(),
+                counter,
+                counter_vault: self.ata(self.mint_b, &counter),
+                config: config_pda(),
+                fee_recipient: self.payer(),
+                fee_ata_b: Some(self.ata(self.mint_b, &self.payer())),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::AcceptCounter {}.data(),
+        };
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &[&maker]).await
+    }
+
+    pub fn bundle(&self, seed: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"bundle", self.maker.pubkey().as_ref(), &seed.to_le_bytes()],
+            &escrow::ID,
+        )
+        .0
+    }
+
+    // The maker bundles `offered` of their tokens for `requested` of the taker's
+    pub async fn make_bundle(
+        &mut self,
+        seed: u64,
+        offered: Vec<escrow::Leg>,
+        requested: Vec<escrow::Leg>,
+    ) -> Result<(), BanksClientError> {
+        let maker = self.maker.pubkey();
+        let bundle = self.bundle(seed);
+        let mut accounts = escrow::accounts::MakeBundle {
+            maker,
+            bundle,
+            config: config_pda(),
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: self.token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        for leg in &offered {
+            accounts.extend([
+                AccountMeta::new_readonly(leg.mint, false),
+                AccountMeta::new(self.ata(leg.mint, &maker), false),
+                AccountMeta::new(self.ata(leg.mint, &bundle), false),
+            ]);
+        }
+        for leg in &requested {
+            accounts.push(AccountMeta::new_readonly(leg.mint, false));
+        }
+
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts,
+            data: escrow::instruction::MakeBundle {
+                seed,
+                offered,
+                requested,
+            }
+            .data(),
+        };
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &[&maker]).await
+    }
+
+    pub async fn take_bundle(
+        &mut self,
+        seed: u64,
+        offered: &[Pubkey],
+        requested: &[Pubkey],
+    ) -> Result<(), BanksClientError> {
+        let (maker, taker) = (self.maker.pubkey(), self.taker.pubkey());
+        let bundle = self.bundle(seed);
+        let mut accounts = escrow::accounts::TakeBundle {
+            taker,
+            maker,
+            bundle,
+            config: config_pda(),
+            fee_recipient: self.payer(),
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: self.token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        for &mint in offered {
+            accounts.extend([
+                AccountMeta::new(mint, false),
+                AccountMeta::new(self.ata(mint, &bundle), false),
+                AccountMeta::new(self.ata(mint, &taker), false),
+            ]);
+        }
+        for &mint in requested {
+            accounts.extend([
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new(self.ata(mint, &taker), false),
+                AccountMeta::new(self.ata(mint, &maker), false),
+                AccountMeta::new(self.ata(mint, &self.payer()), false),
+            ]);
+        }
+
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts,
+            data: escrow::instruction::TakeBundle {}.data(),
+        };
+        let taker = self.taker.insecure_clone();
+        self.send(&[ix], &[&taker]).await
+    }
+
+    // Cancels the bundle at `seed`, signed by `signer`, returning the `offered` legs to them
+    pub async fn refund_bundle(
+        &mut self,
+        signer: &Keypair,
+        seed: u64,
+        offered: &[Pubkey],
+    ) -> Result<(), BanksClientError> {
+        let bundle = self.bundle(seed);
+        let mut accounts = escrow::accounts::RefundBundle {
+            maker: signer.pubkey(),
+            bundle,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: self.token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        for &mint in offered {
+            accounts.extend([
+                AccountMeta::new(mint, false),
+                AccountMeta::new(self.ata(mint, &bundle), false),
+                AccountMeta::new(self.ata(mint, &signer.pubkey()), false),
+            ]);
+        }
+
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts,
+            data: escrow::instruction::RefundBundle {}.data(),
+        };
+        self.send(&[ix], &[signer]).await
+    }
+
+    // The maker takes `amount` of token A back out of the vault at `seed`
+    pub async fn withdraw_from_offer(&mut self, seed: u64, amount: u64) {
+        self.update_offer(seed, None, amount).await.unwrap();
+    }
+
+    // - receive: New total amount of token B to ask for
+    // - withdraw: Amount of token A to take back out of the vault
+    pub async fn update_offer(
+        &mut self,
+        seed: u64,
+        receive: Option<u64>,
+        withdraw: u64,
+    ) -> Result<(), BanksClientError> {
+        let args = escrow::instruction::UpdateOffer {
+            receive,
+            top_up: 0,
+            withdraw,
+            expires_at: None,
+        };
+        self.update_offer_with(seed, args).await
+    }
+
+    pub async fn update_offer_with(
+        &mut self,
+        seed: u64,
+        args: escrow::instruction::UpdateOffer,
+    ) -> Result<(), BanksClientError> {
+        let maker = self.maker.pubkey();
+        let native = self.native_a();
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::UpdateOffer {
+                maker,
+                mint_a: self.mint_a,
+                maker_ata_a: (!native).then(|| self.ata(self.mint_a, &maker)),
+                escrow: self.escrow(seed),
+                vault: self.vault(seed),
+                unwrap_a: (native && args.withdraw > 0).then(|| self.unwrap(seed)),
+                config: config_pda(),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: args.data(),
+        };
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &[&maker]).await
+    }
+
+    // Returns what is left of the expired escrow at `seed` to the maker, sent by the payer
+    pub async fn refund_expired(&mut self, seed: u64) -> Result<(), BanksClientError> {
+        let maker = self.maker.pubkey();
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::RefundExpired {
+                payer: self.payer(),
+                maker,
+                mint_a: self.mint_a,
+                maker_ata_a: Some(self.ata(self.mint_a, &maker)),
+                escrow: self.escrow(seed),
+                vault: self.vault(seed),
+                book: self.book(),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::RefundExpired {}.data(),
+        };
+        self.send(&[ix], &[]).await
+    }
+
+    // Moves the clock to `unix_timestamp`
+    pub async fn warp_to(&mut self, unix_timestamp: i64) {
+        let mut clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp = unix_timestamp;
+        self.ctx.set_sysvar(&clock);
+    }
+
+    // Mints `amount` of `mint` straight into `account`, as anyone could send it there
+    pub async fn donate(&mut self, mint: Pubkey, account: Pubkey, amount: u64) {
+        let ix = spl_token_2022::instruction::mint_to(
+            &self.token_program,
+            &mint,
+            &account,
+            &self.payer(),
+            &[],
+            amount,
+        )
+        .unwrap();
+        self.send(&[ix], &[]).await.unwrap();
+    }
+
+    // An order for DEPOSIT token A at RECEIVE token B, valid for an hour
+    pub async fn order(&mut self, nonce: u64) -> escrow::SignedOrder {
+        escrow::SignedOrder {
+            maker: self.maker.pubkey(),
+            mint_a: self.mint_a,
+            mint_b: self.mint_b,
+            amount_a: DEPOSIT,
+            amount_b: RECEIVE,
+            nonce,
+            expires_at: self.now().await + 3_600,
+        }
+    }
+
+    pub fn nonces(&self, nonce: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"nonces",
+                self.maker.pubkey().as_ref(),
+                &(nonce / escrow::NONCES_PER_PAGE).to_le_bytes(),
+            ],
+            &escrow::ID,
+        )
+        .0
+    }
+
+    // The maker lets the order authority move up to `amount` of their token A
+    pub async fn approve_order_authority(&mut self, amount: u64) {
+        let maker = self.maker.pubkey();
+        let order_authority = Pubkey::find_program_address(&[b"order_authority"], &escrow::ID).0;
+        let ix = spl_token_2022::instruction::approve(
+            &self.token_program,
+            &self.ata(self.mint_a, &maker),
+            &order_authority,
+            &maker,
+            &[],
+            amount,
+        )
+        .unwrap();
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &[&maker]).await.unwrap();
+    }
+
+    // Fills `order` with the Ed25519 check of `signer`'s signature over it in front
+    pub async fn fill_order(
+        &mut self,
+        order: &escrow::SignedOrder,
+        signer: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        let message = order.message().unwrap();
+        let signature = signer.sign_message(&message);
+        let verify = new_ed25519_instruction_with_signature(
+            &message,
+            signature.as_ref().try_into().unwrap(),
+            &signer.pubkey().to_bytes(),
+        );
+
+        let taker = self.taker.pubkey();
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::FillOrder {
+                taker,
+                maker: order.maker,
+                mint_a: order.mint_a,
+                mint_b: order.mint_b,
+                maker_ata_a: self.ata(order.mint_a, &order.maker),
+                maker_ata_b: self.ata(order.mint_b, &order.maker),
+                taker_ata_a: self.ata(order.mint_a, &taker),
+                taker_ata_b: self.ata(order.mint_b, &taker),
+                order_authority: Pubkey::find_program_address(&[b"order_authority"], &escrow::ID).0,
+                nonces: self.nonces(order.nonce),
+                config: config_pda(),
+                fee_recipient: self.payer(),
+                fee_ata_b: self.ata(order.mint_b, &self.payer()),
+                instructions: solana_sdk::sysvar::instructions::ID,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::FillOrder {
+                order: order.clone(),
+            }
+            .data(),
+        };
+        let taker = self.taker.insecure_clone();
+        self.send(&[verify, ix], &[&taker]).await
+    }
+
+    pub async fn cancel_order(&mut self, nonce: u64) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::CancelOrder {
+                maker: self.maker.pubkey(),
+                nonces: self.nonces(nonce),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::CancelOrder { nonce }.data(),
+        };
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &[&maker]).await
+    }
+
+    // Milestone escrow at `seed`, paid by the maker to the taker in token A
+    pub fn milestone_escrow(&self, seed: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"milestone",
+                self.maker.pubkey().as_ref(),
+                &seed.to_le_bytes(),
+            ],
+            &escrow::ID,
+        )
+        .0
+    }
+
+    // - amounts: Tranche of each milestone, all deposited up front
+    pub async fn make_milestones(
+        &mut self,
+        seed: u64,
+        amounts: &[u64],
+        deadline: i64,
+    ) -> Result<(), BanksClientError> {
+        let payer = self.maker.pubkey();
+        let milestone_escrow = self.milestone_escrow(seed);
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::MakeMilestones {
+                payer,
+                mint: self.mint_a,
+                payer_ata: self.ata(self.mint_a, &payer),
+                milestone_escrow,
+                vault: self.ata(self.mint_a, &milestone_escrow),
+                config: config_pda(),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::MakeMilestones {
+                seed,
+                payee: self.taker.pubkey(),
+                milestones: amounts
+                    .iter()
+                    .map(|&amount| escrow::MilestoneTerms {
+                        amount,
+                        description_hash: [0; 32],
+                    })
+                    .collect(),
+                deadline,
+                arbitration: None,
+            }
+            .data(),
+        };
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &[&maker]).await
+    }
+
+    pub async fn release_milestone(
+        &mut self,
+        seed: u64,
+        index: u8,
+    ) -> Result<(), BanksClientError> {
+        let (payer, payee) = (self.maker.pubkey(), self.taker.pubkey());
+        let milestone_escrow = self.milestone_escrow(seed);
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::ReleaseMilestone {
+                payer,
+                payee,
+                mint: self.mint_a,
+                payee_ata: self.ata(self.mint_a, &payee),
+                milestone_escrow,
+                vault: self.ata(self.mint_a, &milestone_escrow),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::ReleaseMilestone { index }.data(),
+        };
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &[&maker]).await
+    }
+
+    pub async fn approve_milestone_refund(
+        &mut self,
+        seed: u64,
+        signer: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::ApproveMilestoneRefund {
+                signer: signer.pubkey(),
+                milestone_escrow: self.milestone_escrow(seed),
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::ApproveMilestoneRefund {}.data(),
+        };
+        self.send(&[ix], &[signer]).await
+    }
+
+    pub async fn refund_milestones(&mut self, seed: u64) -> Result<(), BanksClientError> {
+        let payer = self.maker.pubkey();
+        let milestone_escrow = self.milestone_escrow(seed);
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::RefundMilestones {
+                payer,
+                mint: self.mint_a,
+                payer_ata: self.ata(self.mint_a, &payer),
+                milestone_escrow,
+                vault: self.ata(self.mint_a, &milestone_escrow),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::RefundMilestones {}.data(),
+        };
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &[&maker]).await
+    }
+
+    pub async fn raise_dispute(
+        &mut self,
+        seed: u64,
+        signer: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::RaiseDispute {
+                signer: signer.pubkey(),
+                escrow: self.escrow(seed),
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::RaiseDispute {}.data(),
+        };
+        self.send(&[ix], &[signer]).await
+    }
+
+    // Resolves the dispute on the escrow at `seed` and returns the program's log lines
+    pub async fn resolve_dispute(
+        &mut self,
+        seed: u64,
+        arbiter: &Keypair,
+        maker_share_bps: u16,
+    ) -> Result<Vec<String>, BanksClientError> {
+        let (maker, taker) = (self.maker.pubkey(), self.taker.pubkey());
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::ResolveDispute {
+                arbiter: arbiter.pubkey(),
+                maker,
+                taker,
+                mint_a: self.mint_a,
+                maker_ata_a: self.ata(self.mint_a, &maker),
+                taker_ata_a: self.ata(self.mint_a, &taker),
+                arbiter_ata_a: self.ata(self.mint_a, &arbiter.pubkey()),
+                escrow: self.escrow(seed),
+                vault: self.vault(seed),
+                book: self.book(),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::ResolveDispute { maker_share_bps }.data(),
+        };
+        let blockhash = self.ctx.get_new_latest_blockhash().await.unwrap();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&self.payer()),
+            &[&self.ctx.payer, arbiter],
+            blockhash,
+        );
+        let result = self
+            .ctx
+            .banks_client
+            .process_transaction_with_metadata(tx)
+            .await?;
+        result.result?;
+        Ok(result.metadata.unwrap().log_messages)
+    }
+
+    // Vesting of token A at `seed`, funded by the maker for the taker
+    pub fn vesting(&self, seed: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"vesting",
+                self.maker.pubkey().as_ref(),
+                &seed.to_le_bytes(),
+            ],
+            &escrow::ID,
+        )
+        .0
+    }
+
+    // Vests DEPOSIT token A to the taker over `schedule`, revocable by the maker
+    pub async fn make_vesting(
+        &mut self,
+        seed: u64,
+        schedule: escrow::VestingSchedule,
+    ) -> Result<(), BanksClientError> {
+        let funder = self.maker.pubkey();
+        let vesting = self.vesting(seed);
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::MakeVesting {
+                funder,
+                mint: self.mint_a,
+                funder_ata: self.ata(self.mint_a, &funder),
+                vesting,
+                vault: self.ata(self.mint_a, &vesting),
+                config: config_pda(),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::MakeVesting {
+                seed,
+                beneficiary: self.taker.pubkey(),
+                total: DEPOSIT,
+                schedule,
+                revoker: Some(funder),
+            }
+            .data(),
+        };
+        let maker = self.maker.insecure_clone();
+        self.send(&[ix], &[&maker]).await
+    }
+
+    pub async fn claim_vesting(&mut self, seed: u64) -> Result<(), BanksClientError> {
+        let (funder, beneficiary) = (self.maker.pubkey(), self.taker.pubkey());
+        let vesting = self.vesting(seed);
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::ClaimVesting {
+                beneficiary,
+                funder,
+                mint: self.mint_a,
+                beneficiary_ata: self.ata(self.mint_a, &beneficiary),
+                vesting,
+                vault: self.ata(self.mint_a, &vesting),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::ClaimVesting {}.data(),
+        };
+        let taker = self.taker.insecure_clone();
+        self.send(&[ix], &[&taker]).await
+    }
+
+    pub async fn revoke_vesting(
+        &mut self,
+        seed: u64,
+        revoker: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        let (funder, beneficiary) = (self.maker.pubkey(), self.taker.pubkey());
+        let vesting = self.vesting(seed);
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::RevokeVesting {
+                revoker: revoker.pubkey(),
+                funder,
+                beneficiary,
+                mint: self.mint_a,
+                funder_ata: self.ata(self.mint_a, &funder),
+                beneficiary_ata: self.ata(self.mint_a, &beneficiary),
+                vesting,
+                vault: self.ata(self.mint_a, &vesting),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::RevokeVesting {}.data(),
+        };
+        self.send(&[ix], &[revoker]).await
+    }
+}
+
+// Starts the test validator with the compiled escrow.so deployed through the upgradeable
+// loader, since initialize_config checks the program data for its upgrade authority
+pub async fn start() -> ProgramTestContext {
+    let mut program_test = ProgramTest::default();
+    program_test.add_upgradeable_program_to_genesis("escrow", &escrow::ID);
+    let mut ctx = program_test.start_with_context().await;
+
+    // Genesis deploys with the default pubkey as upgrade authority, so hand it to the payer.
+    // It follows the 4-byte state tag, 8-byte slot and 1-byte option tag of the header.
+    let program_data = get_program_data_address(&escrow::ID);
+    let mut account: AccountSharedData = ctx
+        .banks_client
+        .get_account(program_data)
+        .await
+        .unwrap()
+        .unwrap()
+        .into();
+    account.data_as_mut_slice()[13..45].copy_from_slice(ctx.payer.pubkey().as_ref());
+    ctx.set_account(&program_data, &account);
+    ctx
+}
+
+// Zero-fee config with `admin` as admin and fee recipient
+pub fn initialize_config_ix(admin: Pubkey, fee_bps: u16) -> Instruction {
+    Instruction {
+        program_id: escrow::ID,
+        accounts: escrow::accounts::InitializeConfig {
+            admin,
+            program: escrow::ID,
+            program_data: get_program_data_address(&escrow::ID),
+            config: config_pda(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: escrow::instruction::InitializeConfig {
+            fee_bps,
+            fee_recipient: admin,
+        }
+        .data(),
+    }
+}
+
+pub fn config_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &escrow::ID).0
+}
+
+pub fn nft_metadata(nft_mint: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            nft_mint.as_ref(),
+        ],
+        &mpl_token_metadata::ID,
+    )
+    .0
+}
+
+pub fn nft_master_edition(nft_mint: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            nft_mint.as_ref(),
+            b"edition",
+        ],
+        &mpl_token_metadata::ID,
+    )
+    .0
+}
+
+pub fn custom_error(err: BanksClientError) -> Option<u32> {
+    match err.unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => Some(code),
+        _ => None,
+    }
+}
@@ -0,0 +1,151 @@
+// Pausing the program and handing the config over to a new admin.
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Signer};
+
+use common::*;
+
+#[tokio::test]
+async fn pause_blocks_make_and_take_but_not_refund() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.make(1).await.unwrap();
+    s.make(2).await.unwrap();
+    s.set_paused(true).await.unwrap();
+
+    let err = s.make(3).await.unwrap_err();
+    assert_eq!(custom_error(err), Some(escrow::EscrowError::Paused.into()));
+    let err = s.take(1, 1).await.unwrap_err();
+    assert_eq!(custom_error(err), Some(escrow::EscrowError::Paused.into()));
+
+    let maker = s.maker.insecure_clone();
+    s.refund(&maker, 2).await.unwrap();
+
+    s.set_paused(false).await.unwrap();
+    s.take(1, 1).await.unwrap();
+    assert!(!s.exists(&s.escrow(1)).await);
+}
+
+#[tokio::test]
+async fn pause_blocks_update_offer() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.make(1).await.unwrap();
+    s.set_paused(true).await.unwrap();
+
+    let err = s.update_offer(1, Some(RECEIVE * 2), 0).await.unwrap_err();
+    assert_eq!(custom_error(err), Some(escrow::EscrowError::Paused.into()));
+    assert_eq!(s.escrow_state(1).await.receive, RECEIVE);
+
+    s.set_paused(false).await.unwrap();
+    s.update_offer(1, Some(RECEIVE * 2), 0).await.unwrap();
+    assert_eq!(s.escrow_state(1).await.receive, RECEIVE * 2);
+}
+
+#[tokio::test]
+async fn pause_by_non_admin_fails() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    let outsider = s.taker.insecure_clone();
+    let ix = Instruction {
+        program_id: escrow::ID,
+        accounts: escrow::accounts::UpdateConfig {
+            admin: outsider.pubkey(),
+            config: config_pda(),
+        }
+        .to_account_metas(None),
+        data: escrow::instruction::Pause {}.data(),
+    };
+
+    let err = s.send(&[ix], &[&outsider]).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::Unauthorized.into())
+    );
+}
+
+#[tokio::test]
+async fn pause_blocks_counters_and_bundles() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.make(1).await.unwrap();
+    s.make(2).await.unwrap();
+    s.make_counter(2, DEPOSIT, 400).await.unwrap();
+    s.set_paused(true).await.unwrap();
+
+    let err = s.make_counter(1, DEPOSIT, 400).await.unwrap_err();
+    assert_eq!(custom_error(err), Some(escrow::EscrowError::Paused.into()));
+    let err = s.accept_counter(2).await.unwrap_err();
+    assert_eq!(custom_error(err), Some(escrow::EscrowError::Paused.into()));
+
+    let leg = |mint| escrow::Leg { mint, amount: 1 };
+    let (offered, requested) = (vec![leg(s.mint_a)], vec![leg(s.mint_b)]);
+    let err = s.make_bundle(1, offered, requested).await.unwrap_err();
+    assert_eq!(custom_error(err), Some(escrow::EscrowError::Paused.into()));
+}
+
+#[tokio::test]
+async fn set_admin_takes_effect_once_accepted() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    let new_admin = s.taker.insecure_clone();
+    let outsider = s.maker.insecure_clone();
+    let update_config = |admin: Pubkey, data: Vec<u8>| Instruction {
+        program_id: escrow::ID,
+        accounts: escrow::accounts::UpdateConfig {
+            admin,
+            config: config_pda(),
+        }
+        .to_account_metas(None),
+        data,
+    };
+    let accept_admin = |new_admin: Pubkey| Instruction {
+        program_id: escrow::ID,
+        accounts: escrow::accounts::AcceptAdmin {
+            new_admin,
+            config: config_pda(),
+        }
+        .to_account_metas(None),
+        data: escrow::instruction::AcceptAdmin {}.data(),
+    };
+
+    let set_admin = escrow::instruction::SetAdmin {
+        new_admin: new_admin.pubkey(),
+    };
+    s.send(&[update_config(s.payer(), set_admin.data())], &[])
+        .await
+        .unwrap();
+
+    // Until the offer is accepted the old admin keeps the role
+    let pause = escrow::instruction::Pause {}.data();
+    let err = s
+        .send(
+            &[update_config(new_admin.pubkey(), pause.clone())],
+            &[&new_admin],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::Unauthorized.into())
+    );
+    let err = s
+        .send(&[accept_admin(outsider.pubkey())], &[&outsider])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::Unauthorized.into())
+    );
+
+    s.send(&[accept_admin(new_admin.pubkey())], &[&new_admin])
+        .await
+        .unwrap();
+
+    s.send(
+        &[update_config(new_admin.pubkey(), pause.clone())],
+        &[&new_admin],
+    )
+    .await
+    .unwrap();
+    assert!(s.set_paused(false).await.is_err());
+}
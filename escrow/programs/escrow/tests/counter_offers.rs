@@ -0,0 +1,47 @@
+// Counter-offers from takers, accepted by the maker.
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_spl::token::spl_token;
+use solana_sdk::signature::Signer;
+
+use common::*;
+
+#[tokio::test]
+async fn accept_counter_pays_the_pinned_amount_a() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.make(1).await.unwrap();
+    s.make_counter(1, 600, 400).await.unwrap();
+
+    s.accept_counter(1).await.unwrap();
+
+    // The 400 of token A beyond the counter goes back to the maker
+    let (maker, taker) = (s.maker.pubkey(), s.taker.pubkey());
+    assert_eq!(s.token_balance(&s.ata(s.mint_a, &taker)).await, 600);
+    assert_eq!(
+        s.token_balance(&s.ata(s.mint_a, &maker)).await,
+        DEPOSIT + 400
+    );
+    assert_eq!(s.token_balance(&s.ata(s.mint_b, &maker)).await, 400);
+    assert!(!s.exists(&s.vault(1)).await);
+    assert!(!s.exists(&s.escrow(1)).await);
+    assert!(!s.exists(&s.counter(1)).await);
+    assert!(s.listed_offers().await.is_empty());
+}
+
+#[tokio::test]
+async fn accept_counter_after_draining_the_vault_fails() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.make(1).await.unwrap();
+    s.make_counter(1, DEPOSIT, 400).await.unwrap();
+    s.withdraw_from_offer(1, DEPOSIT - 1).await;
+
+    let err = s.accept_counter(1).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::CounterAmountUnavailable.into())
+    );
+    let counter_vault = s.ata(s.mint_b, &s.counter(1));
+    assert_eq!(s.token_balance(&counter_vault).await, 400);
+}
@@ -0,0 +1,88 @@
+// Disputes on offers with an arbiter, and their timeout.
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_lang::Event;
+use anchor_spl::token::spl_token;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+use common::*;
+
+#[tokio::test]
+async fn dispute_needs_a_paying_taker_and_resolve_splits_the_vault() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    let arbiter = Keypair::new();
+    s.create_ata(s.mint_a, &arbiter.pubkey()).await;
+    s.named_taker = Some(s.taker.pubkey());
+    s.arbitration = Some(escrow::ArbiterTerms {
+        arbiter: arbiter.pubkey(),
+        fee_bps: 100,
+    });
+    s.make(1).await.unwrap();
+
+    let taker = s.taker.insecure_clone();
+    let err = s.raise_dispute(1, &taker).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::NothingToDispute.into())
+    );
+
+    s.take_partial(1, RECEIVE / 2, 0).await.unwrap();
+    s.raise_dispute(1, &taker).await.unwrap();
+    let maker = s.maker.insecure_clone();
+    let err = s.refund(&maker, 1).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::Disputed.into())
+    );
+
+    // Half the vault is left: 1% to the arbiter, then an even split
+    let logs = s.resolve_dispute(1, &arbiter, 5_000).await.unwrap();
+    let (maker_a, taker_a) = (
+        s.ata(s.mint_a, &maker.pubkey()),
+        s.ata(s.mint_a, &taker.pubkey()),
+    );
+    assert_eq!(
+        s.token_balance(&s.ata(s.mint_a, &arbiter.pubkey())).await,
+        5
+    );
+    assert_eq!(s.token_balance(&maker_a).await, DEPOSIT + 247);
+    assert_eq!(s.token_balance(&taker_a).await, DEPOSIT / 2 + 248);
+    assert!(!s.exists(&s.escrow(1)).await);
+
+    let refunded = escrow::OfferRefunded {
+        escrow: s.escrow(1),
+        maker: maker.pubkey(),
+        amount_a: 247,
+    };
+    let expected = format!("Program data: {}", BASE64.encode(refunded.data()));
+    assert!(logs.contains(&expected));
+}
+
+#[tokio::test]
+async fn dispute_stops_blocking_refunds_after_the_timeout() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.named_taker = Some(s.taker.pubkey());
+    s.arbitration = Some(escrow::ArbiterTerms {
+        arbiter: Pubkey::new_unique(),
+        fee_bps: 0,
+    });
+    s.make(1).await.unwrap();
+    s.take_partial(1, RECEIVE / 2, 0).await.unwrap();
+
+    let taker = s.taker.insecure_clone();
+    s.raise_dispute(1, &taker).await.unwrap();
+    let now = s.now().await;
+    s.warp_to(now + escrow::DISPUTE_TIMEOUT).await;
+
+    let maker = s.maker.insecure_clone();
+    s.refund(&maker, 1).await.unwrap();
+    let maker_a = s.ata(s.mint_a, &maker.pubkey());
+    assert_eq!(s.token_balance(&maker_a).await, DEPOSIT + DEPOSIT / 2);
+    assert!(!s.exists(&s.escrow(1)).await);
+}
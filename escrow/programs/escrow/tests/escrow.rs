@@ -0,0 +1,186 @@
+// Integration tests for make, take and refund under SPL Token and Token-2022. Each later
+// feature has its own file next to this one, sharing the setup in common/. The compiled
+// escrow.so runs inside solana-program-test against the real SPL Token, Token-2022 and ATA
+// programs. They only compile with the test-sbf feature, so build and run them with
+// `cargo test-sbf` (or `anchor run test-sbf`).
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_lang::Event;
+use anchor_spl::{token::spl_token, token_2022::spl_token_2022};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+use common::*;
+
+async fn make_then_take(token_program: Pubkey) {
+    let mut s = Setup::new(token_program, RECEIVE, None).await;
+    s.make(1).await.unwrap();
+
+    let (escrow, vault) = (s.escrow(1), s.vault(1));
+    assert_eq!(s.token_balance(&vault).await, DEPOSIT);
+    assert_eq!(s.listed_offers().await, vec![escrow]);
+    let rent = s.lamports(&escrow).await + s.lamports(&vault).await;
+    let maker_before = s.lamports(&s.maker.pubkey()).await;
+
+    s.take(1, 1).await.unwrap();
+
+    let (maker, taker) = (s.maker.pubkey(), s.taker.pubkey());
+    assert_eq!(s.token_balance(&s.ata(s.mint_a, &taker)).await, DEPOSIT);
+    assert_eq!(s.token_balance(&s.ata(s.mint_b, &maker)).await, RECEIVE);
+    assert!(!s.exists(&vault).await);
+    assert!(!s.exists(&escrow).await);
+    assert_eq!(s.lamports(&maker).await, maker_before + rent);
+    assert!(s.listed_offers().await.is_empty());
+}
+
+async fn make_then_refund(token_program: Pubkey) {
+    let mut s = Setup::new(token_program, RECEIVE, None).await;
+    s.make(1).await.unwrap();
+
+    let (escrow, vault) = (s.escrow(1), s.vault(1));
+    let rent = s.lamports(&escrow).await + s.lamports(&vault).await;
+    let maker = s.maker.pubkey();
+    let maker_before = s.lamports(&maker).await;
+
+    let signer = s.maker.insecure_clone();
+    s.refund(&signer, 1).await.unwrap();
+
+    assert_eq!(s.token_balance(&s.ata(s.mint_a, &maker)).await, DEPOSIT * 2);
+    assert!(!s.exists(&vault).await);
+    assert!(!s.exists(&escrow).await);
+    assert_eq!(s.lamports(&maker).await, maker_before + rent);
+    assert!(s.listed_offers().await.is_empty());
+}
+
+#[tokio::test]
+async fn make_then_take_spl_token() {
+    make_then_take(spl_token::ID).await;
+}
+
+#[tokio::test]
+async fn make_then_take_token_2022() {
+    make_then_take(spl_token_2022::ID).await;
+}
+
+#[tokio::test]
+async fn make_then_refund_spl_token() {
+    make_then_refund(spl_token::ID).await;
+}
+
+#[tokio::test]
+async fn make_then_refund_token_2022() {
+    make_then_refund(spl_token_2022::ID).await;
+}
+
+#[tokio::test]
+async fn refund_by_wrong_maker_fails() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.make(1).await.unwrap();
+
+    let intruder = Keypair::new();
+    s.airdrop(&intruder.pubkey(), 1_000_000_000).await;
+    s.create_ata(s.mint_a, &intruder.pubkey()).await;
+
+    assert!(s.refund(&intruder, 1).await.is_err());
+    let vault = s.vault(1);
+    assert_eq!(s.token_balance(&vault).await, DEPOSIT);
+    assert!(s.exists(&s.escrow(1)).await);
+}
+
+#[tokio::test]
+async fn take_with_wrong_seed_fails() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.make(1).await.unwrap();
+
+    let err = s.take(2, 1).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(anchor_lang::error::ErrorCode::ConstraintSeeds as u32)
+    );
+    let vault = s.vault(1);
+    assert_eq!(s.token_balance(&vault).await, DEPOSIT);
+}
+
+#[tokio::test]
+async fn take_with_insufficient_balance_fails() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE - 1, None).await;
+    s.make(1).await.unwrap();
+
+    assert!(s.take(1, 1).await.is_err());
+    let vault = s.vault(1);
+    assert_eq!(s.token_balance(&vault).await, DEPOSIT);
+    assert!(s.exists(&s.escrow(1)).await);
+    let taker_b = s.ata(s.mint_b, &s.taker.pubkey());
+    assert_eq!(s.token_balance(&taker_b).await, RECEIVE - 1);
+}
+
+#[tokio::test]
+async fn make_grosses_up_token_2022_transfer_fee() {
+    // 1% transfer fee on token A
+    let mut s = Setup::new(spl_token_2022::ID, RECEIVE, Some(100)).await;
+    s.make(1).await.unwrap();
+
+    let (escrow, vault) = (s.escrow(1), s.vault(1));
+    assert_eq!(s.token_balance(&vault).await, DEPOSIT);
+
+    // The fee withheld in the vault on deposit is harvested so the vault can be closed,
+    // and the fee on the way out is withheld from the taker
+    s.take(1, 1).await.unwrap();
+
+    let taker_a = s.ata(s.mint_a, &s.taker.pubkey());
+    assert_eq!(s.token_balance(&taker_a).await, DEPOSIT - DEPOSIT / 100);
+    assert!(!s.exists(&vault).await);
+    assert!(!s.exists(&escrow).await);
+}
+
+#[tokio::test]
+async fn refund_closes_token_2022_vault_with_withheld_fee() {
+    // 1% transfer fee on token A, so the maker pays 1011 to deposit 1000
+    let mut s = Setup::new(spl_token_2022::ID, RECEIVE, Some(100)).await;
+    s.make(1).await.unwrap();
+
+    let signer = s.maker.insecure_clone();
+    s.refund(&signer, 1).await.unwrap();
+
+    let maker_a = s.ata(s.mint_a, &s.maker.pubkey());
+    assert_eq!(
+        s.token_balance(&maker_a).await,
+        DEPOSIT * 2 - 1_011 + DEPOSIT - DEPOSIT / 100
+    );
+    assert!(!s.exists(&s.vault(1)).await);
+    assert!(!s.exists(&s.escrow(1)).await);
+}
+
+#[tokio::test]
+async fn make_and_take_emit_events() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.make(1).await.unwrap();
+    let created = escrow::OfferCreated {
+        escrow: s.escrow(1),
+        maker: s.maker.pubkey(),
+        seed: 1,
+        mint_a: s.mint_a,
+        mint_b: s.mint_b,
+        deposit: DEPOSIT,
+        receive: RECEIVE,
+        expires_at: s.escrow_state(1).await.expires_at,
+    };
+    let expected = format!("Program data: {}", BASE64.encode(created.data()));
+    assert!(s.logs.contains(&expected));
+
+    s.take(1, 1).await.unwrap();
+    let taken = escrow::OfferTaken {
+        escrow: s.escrow(1),
+        taker: s.taker.pubkey(),
+        amount_a: DEPOSIT,
+        amount_b: RECEIVE,
+        remaining: 0,
+    };
+    let expected = format!("Program data: {}", BASE64.encode(taken.data()));
+    assert!(s.logs.contains(&expected));
+}
@@ -0,0 +1,36 @@
+// Expiring offers and the permissionless refund once they expire.
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_spl::token::spl_token;
+use solana_sdk::signature::Signer;
+
+use common::*;
+
+#[tokio::test]
+async fn refund_expired_returns_the_deposit_once_the_offer_expired() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.make(1).await.unwrap();
+
+    let err = s.refund_expired(1).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::OfferNotExpired.into())
+    );
+
+    let expires_at = s.escrow_state(1).await.expires_at;
+    s.warp_to(expires_at).await;
+    let err = s.take(1, 1).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::OfferExpired.into())
+    );
+
+    // Anyone can crank it, here the context payer
+    s.refund_expired(1).await.unwrap();
+    let maker_a = s.ata(s.mint_a, &s.maker.pubkey());
+    assert_eq!(s.token_balance(&maker_a).await, DEPOSIT * 2);
+    assert!(!s.exists(&s.escrow(1)).await);
+    assert!(s.listed_offers().await.is_empty());
+}
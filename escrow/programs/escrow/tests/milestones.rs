@@ -0,0 +1,56 @@
+// Milestone escrows released in tranches, or refunded by agreement or after the deadline.
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_spl::token::spl_token;
+use solana_sdk::signature::Signer;
+
+use common::*;
+
+#[tokio::test]
+async fn milestones_release_in_tranches_and_the_last_sweeps_donations() {
+    let mut s = Setup::new(spl_token::ID, 0, None).await;
+    let deadline = s.now().await + 3_600;
+    s.make_milestones(1, &[300, 700], deadline).await.unwrap();
+    let taker_a = s.ata(s.mint_a, &s.taker.pubkey());
+
+    s.release_milestone(1, 0).await.unwrap();
+    assert_eq!(s.token_balance(&taker_a).await, 300);
+    let err = s.release_milestone(1, 0).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::InvalidMilestone.into())
+    );
+
+    // Tokens sent straight to the vault can't keep it from closing
+    let vault = s.ata(s.mint_a, &s.milestone_escrow(1));
+    s.donate(s.mint_a, vault, 50).await;
+    s.release_milestone(1, 1).await.unwrap();
+    assert_eq!(s.token_balance(&taker_a).await, DEPOSIT + 50);
+    assert!(!s.exists(&vault).await);
+    assert!(!s.exists(&s.milestone_escrow(1)).await);
+}
+
+#[tokio::test]
+async fn milestones_refund_needs_both_approvals_before_the_deadline() {
+    let mut s = Setup::new(spl_token::ID, 0, None).await;
+    let deadline = s.now().await + 3_600;
+    s.make_milestones(1, &[300, 700], deadline).await.unwrap();
+    s.release_milestone(1, 0).await.unwrap();
+
+    let maker = s.maker.insecure_clone();
+    s.approve_milestone_refund(1, &maker).await.unwrap();
+    let err = s.refund_milestones(1).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::RefundNotAllowed.into())
+    );
+
+    let taker = s.taker.insecure_clone();
+    s.approve_milestone_refund(1, &taker).await.unwrap();
+    s.refund_milestones(1).await.unwrap();
+    let maker_a = s.ata(s.mint_a, &s.maker.pubkey());
+    assert_eq!(s.token_balance(&maker_a).await, DEPOSIT * 2 - 300);
+    assert!(!s.exists(&s.milestone_escrow(1)).await);
+}
@@ -0,0 +1,84 @@
+// Native SOL offers, wrapped on make and unwrapped through a temporary account.
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_lang::InstructionData;
+use anchor_spl::token::spl_token::{self, native_mint};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    signature::Signer,
+};
+
+use common::*;
+
+#[tokio::test]
+async fn native_sol_is_unwrapped_without_touching_the_takers_wsol() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.offer_native_sol();
+    let (maker, taker) = (s.maker.pubkey(), s.taker.pubkey());
+
+    let maker_lamports = s.lamports(&maker).await;
+    s.make(1).await.unwrap();
+    assert_eq!(s.token_balance(&s.vault(1)).await, DEPOSIT);
+    assert!(s.lamports(&maker).await <= maker_lamports - DEPOSIT);
+
+    // wSOL the taker already holds stays wrapped
+    let taker_wsol = s.create_ata(native_mint::ID, &taker).await;
+    s.airdrop(&taker_wsol, 5_000).await;
+    let sync = spl_token::instruction::sync_native(&spl_token::ID, &taker_wsol).unwrap();
+    s.send(&[sync], &[]).await.unwrap();
+
+    let taker_lamports = s.lamports(&taker).await;
+    s.take(1, 1).await.unwrap();
+    assert_eq!(s.lamports(&taker).await, taker_lamports + DEPOSIT);
+    assert_eq!(s.token_balance(&taker_wsol).await, 5_000);
+    assert!(!s.exists(&s.unwrap(1)).await);
+    assert!(!s.exists(&s.escrow(1)).await);
+}
+
+#[tokio::test]
+async fn native_sol_take_needs_the_unwrap_account() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.offer_native_sol();
+    s.make(1).await.unwrap();
+
+    // Leave the optional unwrap account out
+    let unwrap = s.unwrap(1);
+    let mut accounts = s.take_accounts(1);
+    for meta in accounts.iter_mut().filter(|m| m.pubkey == unwrap) {
+        *meta = AccountMeta::new_readonly(escrow::ID, false);
+    }
+    let ix = Instruction {
+        program_id: escrow::ID,
+        accounts,
+        data: escrow::instruction::Take {
+            _seed: 1,
+            max_amount_b: u64::MAX,
+            min_amount_a: 0,
+            proof: vec![],
+        }
+        .data(),
+    };
+    let taker = s.taker.insecure_clone();
+    let err = s.send(&[ix], &[&taker]).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::MissingTokenAccount.into())
+    );
+    assert_eq!(s.token_balance(&s.vault(1)).await, DEPOSIT);
+}
+
+#[tokio::test]
+async fn update_offer_unwraps_native_sol_withdrawals() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.offer_native_sol();
+    s.make(1).await.unwrap();
+
+    let maker = s.maker.pubkey();
+    let maker_lamports = s.lamports(&maker).await;
+    s.withdraw_from_offer(1, DEPOSIT / 4).await;
+    assert_eq!(s.lamports(&maker).await, maker_lamports + DEPOSIT / 4);
+    assert_eq!(s.token_balance(&s.vault(1)).await, DEPOSIT - DEPOSIT / 4);
+    assert!(!s.exists(&s.unwrap(1)).await);
+}
@@ -0,0 +1,39 @@
+// The paged offer book escrows are listed on.
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_spl::token::spl_token;
+
+use common::*;
+
+#[tokio::test]
+async fn offer_book_pages_are_created_in_order() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+
+    // Page 1 can't be opened before page 0 exists
+    let err = s
+        .make_on_page(1, RECEIVE, false, 1, None)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::OfferBookPageSkipped.into())
+    );
+
+    s.make(1).await.unwrap();
+    let err = s
+        .make_on_page(2, RECEIVE, false, 1, None)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::OfferBookPageSkipped.into())
+    );
+
+    let page_0 = s.book_page(0);
+    s.make_on_page(2, RECEIVE, false, 1, Some(page_0))
+        .await
+        .unwrap();
+    assert!(s.exists(&s.book_page(1)).await);
+}
@@ -0,0 +1,59 @@
+// Partial fills through take_partial, paid out pro rata until the offer is filled.
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_lang::Event;
+use anchor_spl::token::spl_token;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use solana_sdk::signature::Signer;
+
+use common::*;
+
+#[tokio::test]
+async fn take_partial_fills_pro_rata_and_closes_on_the_last_fill() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.make(1).await.unwrap();
+    let (maker, taker) = (s.maker.pubkey(), s.taker.pubkey());
+
+    s.take_partial(1, RECEIVE / 5, 0).await.unwrap();
+    assert_eq!(s.token_balance(&s.ata(s.mint_a, &taker)).await, DEPOSIT / 5);
+    assert_eq!(s.escrow_state(1).await.remaining, RECEIVE - RECEIVE / 5);
+    let taken = escrow::OfferTaken {
+        escrow: s.escrow(1),
+        taker,
+        amount_a: DEPOSIT / 5,
+        amount_b: RECEIVE / 5,
+        remaining: RECEIVE - RECEIVE / 5,
+    };
+    let expected = format!("Program data: {}", BASE64.encode(taken.data()));
+    assert!(s.logs.contains(&expected));
+
+    s.take_partial(1, RECEIVE - RECEIVE / 5, 0).await.unwrap();
+    assert_eq!(s.token_balance(&s.ata(s.mint_a, &taker)).await, DEPOSIT);
+    assert_eq!(s.token_balance(&s.ata(s.mint_b, &maker)).await, RECEIVE);
+    assert!(!s.exists(&s.vault(1)).await);
+    assert!(!s.exists(&s.escrow(1)).await);
+    assert!(s.listed_offers().await.is_empty());
+}
+
+#[tokio::test]
+async fn take_partial_rejects_overfills_and_short_token_a() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE * 2, None).await;
+    s.make(1).await.unwrap();
+
+    let err = s.take_partial(1, RECEIVE + 1, 0).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::FillExceedsRemaining.into())
+    );
+    let err = s
+        .take_partial(1, RECEIVE / 5, DEPOSIT / 5 + 1)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::SlippageExceeded.into())
+    );
+    assert_eq!(s.token_balance(&s.vault(1)).await, DEPOSIT);
+}
@@ -0,0 +1,122 @@
+// The protocol fee config and the fee taken on completed trades.
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_spl::token::spl_token;
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+
+use common::*;
+
+#[tokio::test]
+async fn initialize_config_requires_the_upgrade_authority() {
+    let ctx = start().await;
+    let squatter = Keypair::new();
+    let fund = system_instruction::transfer(&ctx.payer.pubkey(), &squatter.pubkey(), 1_000_000_000);
+    let ix = initialize_config_ix(squatter.pubkey(), 0);
+    let tx = Transaction::new_signed_with_payer(
+        &[fund, ix],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer, &squatter],
+        ctx.last_blockhash,
+    );
+
+    let err = ctx.banks_client.process_transaction(tx).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::Unauthorized.into())
+    );
+    assert!(ctx
+        .banks_client
+        .get_account(config_pda())
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn fee_above_the_cap_is_rejected() {
+    let ctx = start().await;
+    let ix = initialize_config_ix(ctx.payer.pubkey(), escrow::MAX_FEE_BPS + 1);
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(tx).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::InvalidFee.into())
+    );
+
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    let err = s.set_fee(escrow::MAX_FEE_BPS + 1).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::InvalidFee.into())
+    );
+    s.set_fee(escrow::MAX_FEE_BPS).await.unwrap();
+}
+
+#[tokio::test]
+async fn take_takes_the_protocol_fee() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.set_fee(100).await.unwrap();
+    s.make(1).await.unwrap();
+
+    s.take(1, 1).await.unwrap();
+
+    let (maker, taker) = (s.maker.pubkey(), s.taker.pubkey());
+    assert_eq!(s.token_balance(&s.ata(s.mint_b, &maker)).await, RECEIVE - 5);
+    assert_eq!(s.token_balance(&s.ata(s.mint_b, &s.payer())).await, 5);
+    assert_eq!(s.token_balance(&s.ata(s.mint_b, &taker)).await, 0);
+    assert_eq!(s.token_balance(&s.ata(s.mint_a, &taker)).await, DEPOSIT);
+}
+
+#[tokio::test]
+async fn accept_counter_takes_the_protocol_fee() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.set_fee(100).await.unwrap();
+    s.make(1).await.unwrap();
+    s.make_counter(1, DEPOSIT, 400).await.unwrap();
+
+    s.accept_counter(1).await.unwrap();
+
+    let maker_b = s.ata(s.mint_b, &s.maker.pubkey());
+    let fee_b = s.ata(s.mint_b, &s.payer());
+    assert_eq!(s.token_balance(&maker_b).await, 396);
+    assert_eq!(s.token_balance(&fee_b).await, 4);
+    assert_eq!(
+        s.token_balance(&s.ata(s.mint_a, &s.taker.pubkey())).await,
+        DEPOSIT
+    );
+}
+
+#[tokio::test]
+async fn take_bundle_takes_the_protocol_fee() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.set_fee(100).await.unwrap();
+    let (mint_a, mint_b) = (s.mint_a, s.mint_b);
+    let offered = vec![escrow::Leg {
+        mint: mint_a,
+        amount: DEPOSIT,
+    }];
+    let requested = vec![escrow::Leg {
+        mint: mint_b,
+        amount: RECEIVE,
+    }];
+    s.make_bundle(1, offered, requested).await.unwrap();
+
+    s.take_bundle(1, &[mint_a], &[mint_b]).await.unwrap();
+
+    let (maker, taker) = (s.maker.pubkey(), s.taker.pubkey());
+    assert_eq!(s.token_balance(&s.ata(mint_a, &taker)).await, DEPOSIT);
+    assert_eq!(s.token_balance(&s.ata(mint_b, &maker)).await, RECEIVE - 5);
+    assert_eq!(s.token_balance(&s.ata(mint_b, &s.payer())).await, 5);
+    assert!(!s.exists(&s.bundle(1)).await);
+}
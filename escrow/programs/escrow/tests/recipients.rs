@@ -0,0 +1,42 @@
+// Proceeds routed to a maker recipient other than the maker.
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_spl::token::spl_token;
+use solana_sdk::{account::AccountSharedData, pubkey::Pubkey, signature::Signer};
+
+use common::*;
+
+#[tokio::test]
+async fn accept_counter_pays_the_maker_recipient() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    let cold_wallet = Pubkey::new_unique();
+    s.maker_recipient = Some(cold_wallet);
+    s.make(1).await.unwrap();
+    s.make_counter(1, DEPOSIT, 400).await.unwrap();
+
+    s.accept_counter(1).await.unwrap();
+
+    assert_eq!(s.token_balance(&s.ata(s.mint_b, &cold_wallet)).await, 400);
+    assert_eq!(
+        s.token_balance(&s.ata(s.mint_b, &s.maker.pubkey())).await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn take_pays_a_program_owned_maker_recipient() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    // Stands in for a PDA such as a multisig vault, owned by another program
+    let vault_pda = Pubkey::new_unique();
+    let account = AccountSharedData::new(1_000_000, 8, &Pubkey::new_unique());
+    s.ctx.set_account(&vault_pda, &account);
+    s.maker_recipient = Some(vault_pda);
+    s.make(1).await.unwrap();
+
+    s.take(1, 1).await.unwrap();
+
+    assert_eq!(s.token_balance(&s.ata(s.mint_b, &vault_pda)).await, RECEIVE);
+    assert!(!s.exists(&s.escrow(1)).await);
+}
@@ -0,0 +1,74 @@
+// Off-chain signed orders settled on-chain with fill_order.
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_spl::{token::spl_token, token_2022::spl_token_2022};
+use solana_sdk::signature::Signer;
+
+use common::*;
+
+#[tokio::test]
+async fn fill_order_settles_a_signed_order_once() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE * 2, None).await;
+    s.approve_order_authority(DEPOSIT * 2).await;
+    let (maker, taker) = (s.maker.pubkey(), s.taker.pubkey());
+    let order = s.order(7).await;
+
+    let signer = s.maker.insecure_clone();
+    s.fill_order(&order, &signer).await.unwrap();
+    assert_eq!(s.token_balance(&s.ata(s.mint_a, &taker)).await, DEPOSIT);
+    assert_eq!(s.token_balance(&s.ata(s.mint_b, &maker)).await, RECEIVE);
+
+    // The same signature can't be settled twice
+    let err = s.fill_order(&order, &signer).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::OrderNonceUsed.into())
+    );
+    assert_eq!(s.token_balance(&s.ata(s.mint_a, &taker)).await, DEPOSIT);
+}
+
+#[tokio::test]
+async fn fill_order_rejects_other_signers_and_cancelled_orders() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.approve_order_authority(DEPOSIT).await;
+
+    let order = s.order(7).await;
+    let impostor = s.taker.insecure_clone();
+    let err = s.fill_order(&order, &impostor).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::InvalidOrderSignature.into())
+    );
+
+    s.cancel_order(7).await.unwrap();
+    let signer = s.maker.insecure_clone();
+    let err = s.fill_order(&order, &signer).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::OrderNonceUsed.into())
+    );
+    let err = s.cancel_order(7).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::OrderNonceUsed.into())
+    );
+}
+
+#[tokio::test]
+async fn fill_order_rejects_unsupported_mint_extensions() {
+    let mut s = Setup::new(spl_token_2022::ID, RECEIVE, None).await;
+    s.mint_a = s.create_mint_with(None, true).await;
+    let maker = s.maker.pubkey();
+    s.mint_to(s.mint_a, &maker, DEPOSIT).await;
+    s.approve_order_authority(DEPOSIT).await;
+
+    let order = s.order(7).await;
+    let signer = s.maker.insecure_clone();
+    let err = s.fill_order(&order, &signer).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::UnsupportedMintExtension.into())
+    );
+}
@@ -0,0 +1,162 @@
+// Batch fills across several offers with take_many, bounded by a budget and a price.
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_lang::solana_program::hash::hashv;
+use anchor_spl::{token::spl_token, token_2022::spl_token_2022};
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+use common::*;
+
+// Price of every test offer: RECEIVE token B for DEPOSIT token A, scaled by PRICE_SCALE
+const OFFER_PRICE: u64 = RECEIVE * escrow::PRICE_SCALE / DEPOSIT;
+
+#[tokio::test]
+async fn take_many_rejects_native_sol_offers() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.offer_native_sol();
+    s.make(1).await.unwrap();
+
+    let err = s
+        .take_many(&[1], RECEIVE, u64::MAX, false, vec![])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::NativeMintUnsupported.into())
+    );
+    assert_eq!(s.token_balance(&s.vault(1)).await, DEPOSIT);
+}
+
+#[tokio::test]
+async fn take_many_fills_in_order_up_to_budget() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE * 2, None).await;
+    s.make(1).await.unwrap();
+    s.make(2).await.unwrap();
+    let (first, second) = (s.escrow(1), s.escrow(2));
+
+    s.take_many(&[1, 2], RECEIVE + RECEIVE / 2, OFFER_PRICE, false, vec![])
+        .await
+        .unwrap();
+
+    let (maker, taker) = (s.maker.pubkey(), s.taker.pubkey());
+    assert_eq!(
+        s.token_balance(&s.ata(s.mint_a, &taker)).await,
+        DEPOSIT + DEPOSIT / 2
+    );
+    assert_eq!(
+        s.token_balance(&s.ata(s.mint_b, &maker)).await,
+        RECEIVE + RECEIVE / 2
+    );
+    // The first offer is fully filled and closed, the second stays open and listed
+    assert!(!s.exists(&first).await);
+    assert!(!s.exists(&s.vault(1)).await);
+    let vault = s.vault(2);
+    assert_eq!(s.token_balance(&vault).await, DEPOSIT / 2);
+    assert_eq!(s.listed_offers().await, vec![second]);
+}
+
+#[tokio::test]
+async fn take_many_respects_price_limit() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.make(1).await.unwrap();
+
+    let err = s
+        .take_many(&[1], RECEIVE, OFFER_PRICE - 1, false, vec![])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::PriceTooHigh.into())
+    );
+
+    // Skipping leaves the offer untouched
+    s.take_many(&[1], RECEIVE, OFFER_PRICE - 1, true, vec![])
+        .await
+        .unwrap();
+    let vault = s.vault(1);
+    assert_eq!(s.token_balance(&vault).await, DEPOSIT);
+    let taker_b = s.ata(s.mint_b, &s.taker.pubkey());
+    assert_eq!(s.token_balance(&taker_b).await, RECEIVE);
+}
+
+#[tokio::test]
+async fn take_many_checks_each_offers_allowlist_proof() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE * 2, None).await;
+    let taker_leaf = hashv(&[s.taker.pubkey().as_ref()]).to_bytes();
+    let other_leaf = hashv(&[Pubkey::new_unique().as_ref()]).to_bytes();
+    let (low, high) = (taker_leaf.min(other_leaf), taker_leaf.max(other_leaf));
+    s.allowlist = Some(hashv(&[&low, &high]).to_bytes());
+    s.make(1).await.unwrap();
+    s.allowlist = None;
+    s.make(2).await.unwrap();
+
+    let err = s
+        .take_many(&[1, 2], RECEIVE * 2, u64::MAX, false, vec![])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::UnauthorizedTaker.into())
+    );
+
+    // Offer 2 has no allowlist, so it needs no proof
+    let budget = RECEIVE + RECEIVE / 2;
+    s.take_many(&[1, 2], budget, u64::MAX, false, vec![vec![other_leaf]])
+        .await
+        .unwrap();
+    let taker_a = s.ata(s.mint_a, &s.taker.pubkey());
+    assert_eq!(s.token_balance(&taker_a).await, DEPOSIT + DEPOSIT / 2);
+    assert!(!s.exists(&s.escrow(1)).await);
+}
+
+#[tokio::test]
+async fn take_many_budget_covers_token_2022_fee_on_token_b() {
+    let mut s = Setup::new(spl_token_2022::ID, 0, None).await;
+    let (maker, taker) = (s.maker.pubkey(), s.taker.pubkey());
+    // 1% transfer fee on token B
+    s.mint_b = s.create_mint(Some(100)).await;
+    s.mint_to(s.mint_b, &taker, 1_000).await;
+    s.create_ata(s.mint_b, &maker).await;
+    s.make(1).await.unwrap();
+    s.make(2).await.unwrap();
+
+    // Offer 1 nets the maker 500 for 506; the 94 left in the budget buy 93 of offer 2
+    s.take_many(&[1, 2], 600, u64::MAX, false, vec![])
+        .await
+        .unwrap();
+
+    assert_eq!(s.token_balance(&s.ata(s.mint_b, &taker)).await, 400);
+    assert_eq!(s.token_balance(&s.ata(s.mint_b, &maker)).await, 593);
+    assert!(!s.exists(&s.escrow(1)).await);
+    assert!(s.exists(&s.escrow(2)).await);
+}
+
+#[tokio::test]
+async fn take_many_trims_fills_that_fees_round_past_the_budget() {
+    let mut s = Setup::new(spl_token_2022::ID, 0, None).await;
+    let (maker, taker) = (s.maker.pubkey(), s.taker.pubkey());
+    // 1% transfer fee on token B and a 1% protocol fee
+    s.mint_b = s.create_mint(Some(100)).await;
+    s.mint_to(s.mint_b, &taker, 1_000).await;
+    s.create_ata(s.mint_b, &maker).await;
+    s.set_fee(100).await.unwrap();
+    s.make(1).await.unwrap();
+    s.make(2).await.unwrap();
+
+    // Offer 1 costs 506 for 495 to the maker and 5 in fees. Of the 104 left, 102 is sized
+    // for offer 2 but its fees round it up to 105, so it is trimmed to 101, costing 104.
+    s.take_many(&[1, 2], 610, u64::MAX, false, vec![])
+        .await
+        .unwrap();
+
+    assert_eq!(s.token_balance(&s.ata(s.mint_b, &taker)).await, 390);
+    assert_eq!(s.token_balance(&s.ata(s.mint_b, &maker)).await, 595);
+    assert_eq!(s.token_balance(&s.ata(s.mint_b, &s.payer())).await, 6);
+    assert_eq!(
+        s.token_balance(&s.ata(s.mint_a, &taker)).await,
+        DEPOSIT + 202
+    );
+    assert_eq!(s.escrow_state(2).await.remaining, RECEIVE - 101);
+}
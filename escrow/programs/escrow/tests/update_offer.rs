@@ -0,0 +1,132 @@
+// Amending an open offer with update_offer, and the limits takers set against it.
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_lang::Event;
+use anchor_spl::{token::spl_token, token_2022::spl_token_2022};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use solana_sdk::signature::Signer;
+
+use common::*;
+
+#[tokio::test]
+async fn update_offer_amends_price_deposit_and_expiry() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.make(1).await.unwrap();
+    let expires_at = s.escrow_state(1).await.expires_at;
+
+    s.update_offer_with(
+        1,
+        escrow::instruction::UpdateOffer {
+            receive: Some(RECEIVE * 2),
+            top_up: DEPOSIT / 2,
+            withdraw: 0,
+            expires_at: Some(expires_at + 60),
+        },
+    )
+    .await
+    .unwrap();
+    let escrow = s.escrow_state(1).await;
+    assert_eq!(escrow.receive, RECEIVE * 2);
+    assert_eq!(escrow.remaining, RECEIVE * 2);
+    assert_eq!(escrow.expires_at, expires_at + 60);
+    assert_eq!(s.token_balance(&s.vault(1)).await, DEPOSIT + DEPOSIT / 2);
+    let updated = escrow::OfferUpdated {
+        escrow: s.escrow(1),
+        maker: s.maker.pubkey(),
+        receive: RECEIVE * 2,
+        remaining: RECEIVE * 2,
+        deposit: DEPOSIT + DEPOSIT / 2,
+        expires_at: expires_at + 60,
+    };
+    let expected = format!("Program data: {}", BASE64.encode(updated.data()));
+    assert!(s.logs.contains(&expected));
+
+    s.withdraw_from_offer(1, DEPOSIT).await;
+    assert_eq!(s.token_balance(&s.vault(1)).await, DEPOSIT / 2);
+}
+
+#[tokio::test]
+async fn update_offer_rejects_shorter_expiry_and_emptying_the_vault() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE, None).await;
+    s.make(1).await.unwrap();
+    let expires_at = s.escrow_state(1).await.expires_at;
+
+    let err = s
+        .update_offer_with(
+            1,
+            escrow::instruction::UpdateOffer {
+                receive: None,
+                top_up: 0,
+                withdraw: 0,
+                expires_at: Some(expires_at - 1),
+            },
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::InvalidExpiry.into())
+    );
+
+    let err = s.update_offer(1, None, DEPOSIT).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::InvalidAmount.into())
+    );
+    assert_eq!(s.token_balance(&s.vault(1)).await, DEPOSIT);
+}
+
+#[tokio::test]
+async fn take_rejects_terms_amended_past_the_takers_limits() {
+    let mut s = Setup::new(spl_token::ID, RECEIVE * 2, None).await;
+    s.make(1).await.unwrap();
+
+    // The maker raises the price, then pulls half the deposit
+    s.update_offer(1, Some(RECEIVE * 2), 0).await.unwrap();
+    let err = s.take_with_limits(1, 1, RECEIVE, 0).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::SlippageExceeded.into())
+    );
+    s.update_offer(1, None, DEPOSIT / 2).await.unwrap();
+    let err = s
+        .take_with_limits(1, 1, RECEIVE * 2, DEPOSIT)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::SlippageExceeded.into())
+    );
+
+    // Terms within the limits still fill
+    s.take_with_limits(1, 1, RECEIVE * 2, DEPOSIT / 2)
+        .await
+        .unwrap();
+    let taker_a = s.ata(s.mint_a, &s.taker.pubkey());
+    assert_eq!(s.token_balance(&taker_a).await, DEPOSIT / 2);
+}
+
+#[tokio::test]
+async fn take_bounds_the_gross_cost_of_a_transfer_fee_mint() {
+    let mut s = Setup::new(spl_token_2022::ID, 0, None).await;
+    let (maker, taker) = (s.maker.pubkey(), s.taker.pubkey());
+    // 1% transfer fee on token B, so netting the maker 500 costs the taker 506
+    s.mint_b = s.create_mint(Some(100)).await;
+    s.mint_to(s.mint_b, &taker, 1_000).await;
+    s.create_ata(s.mint_b, &maker).await;
+    s.make(1).await.unwrap();
+
+    let err = s.take_with_limits(1, 1, 505, 0).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::SlippageExceeded.into())
+    );
+
+    s.take_with_limits(1, 1, 506, 0).await.unwrap();
+    let taker_b = s.ata(s.mint_b, &taker);
+    assert_eq!(s.token_balance(&taker_b).await, 1_000 - 506);
+    let maker_b = s.ata(s.mint_b, &maker);
+    assert_eq!(s.token_balance(&maker_b).await, RECEIVE);
+}
@@ -0,0 +1,96 @@
+// Linear vesting with a cliff, claims and revocation.
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_spl::token::spl_token;
+use solana_sdk::signature::Signer;
+
+use common::*;
+
+#[tokio::test]
+async fn vesting_claims_follow_the_schedule_and_the_last_sweeps_donations() {
+    let mut s = Setup::new(spl_token::ID, 0, None).await;
+    let start = s.now().await;
+    let schedule = escrow::VestingSchedule {
+        start,
+        cliff: start + 100,
+        end: start + 1_000,
+    };
+    s.make_vesting(1, schedule).await.unwrap();
+    let taker_a = s.ata(s.mint_a, &s.taker.pubkey());
+
+    let err = s.claim_vesting(1).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::NothingToClaim.into())
+    );
+
+    s.warp_to(start + 500).await;
+    s.claim_vesting(1).await.unwrap();
+    assert_eq!(s.token_balance(&taker_a).await, DEPOSIT / 2);
+
+    // Tokens sent straight to the vault can't keep it from closing
+    let vault = s.ata(s.mint_a, &s.vesting(1));
+    s.donate(s.mint_a, vault, 30).await;
+    s.warp_to(start + 1_000).await;
+    s.claim_vesting(1).await.unwrap();
+    assert_eq!(s.token_balance(&taker_a).await, DEPOSIT + 30);
+    assert!(!s.exists(&vault).await);
+    assert!(!s.exists(&s.vesting(1)).await);
+}
+
+#[tokio::test]
+async fn vesting_revoke_is_limited_to_the_revoker_and_sweeps_donations() {
+    let mut s = Setup::new(spl_token::ID, 0, None).await;
+    let start = s.now().await;
+    let schedule = escrow::VestingSchedule {
+        start,
+        cliff: start,
+        end: start + 1_000,
+    };
+    s.make_vesting(1, schedule).await.unwrap();
+    s.create_ata(s.mint_a, &s.taker.pubkey()).await;
+
+    let taker = s.taker.insecure_clone();
+    let err = s.revoke_vesting(1, &taker).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::Unauthorized.into())
+    );
+
+    s.warp_to(start + 250).await;
+    let vault = s.ata(s.mint_a, &s.vesting(1));
+    s.donate(s.mint_a, vault, 40).await;
+    let maker = s.maker.insecure_clone();
+    s.revoke_vesting(1, &maker).await.unwrap();
+
+    let (maker_a, taker_a) = (
+        s.ata(s.mint_a, &maker.pubkey()),
+        s.ata(s.mint_a, &taker.pubkey()),
+    );
+    assert_eq!(s.token_balance(&taker_a).await, DEPOSIT / 4);
+    assert_eq!(
+        s.token_balance(&maker_a).await,
+        DEPOSIT + DEPOSIT - DEPOSIT / 4 + 40
+    );
+    assert!(!s.exists(&vault).await);
+    assert!(!s.exists(&s.vesting(1)).await);
+}
+
+#[tokio::test]
+async fn make_vesting_rejects_a_schedule_too_long_to_measure() {
+    let mut s = Setup::new(spl_token::ID, 0, None).await;
+    let schedule = escrow::VestingSchedule {
+        start: i64::MIN,
+        cliff: i64::MIN,
+        end: i64::MAX,
+    };
+
+    let err = s.make_vesting(1, schedule).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(escrow::EscrowError::InvalidSchedule.into())
+    );
+    assert!(!s.exists(&s.vesting(1)).await);
+}
@@ -1,13 +1,39 @@
-#[allow(unexpected_cfgs)]
 use anchor_lang::{
     prelude::*,
     system_program::{transfer, Transfer},
 };
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::Token,
+    token_2022::{
+        spl_token_2022::{
+            self,
+            extension::{
+                transfer_fee::TransferFeeAmount, BaseStateWithExtensions, StateWithExtensions,
+            },
+        },
+        Token2022,
+    },
+    token_2022_extensions::{harvest_withheld_tokens_to_mint, HarvestWithheldTokensToMint},
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
 
 // Program ID generated during 'anchor init'
 // This is the unique identifier for this program on the Solana blockchain
 declare_id!("2FmfXbj5gPvLD3vjmKKHMsqo14K3VvzxWcJ1zD5pZ8G9");
 
+// How long a looser withdrawal limit waits before it applies (1 day)
+// Gives the owner time to notice and react if their key is compromised
+#[constant]
+pub const LIMIT_INCREASE_DELAY: i64 = 86_400;
+
+// Most members a multisig vault can have, which sizes its member and approval lists
+#[constant]
+pub const MAX_MEMBERS: usize = 10;
+
 // ------------------------------------------------------------------------------------------
 // PROGRAM ENTRYPOINTS
 // ------------------------------------------------------------------------------------------
@@ -21,8 +47,25 @@ pub mod vault {
     // This creates two Program Derived Addresses (PDAs):
     // 1. vault_state - Stores metadata about the vault (bump seeds)
     // 2. vault - The actual address that will hold the user's funds
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-        ctx.accounts.initialize(&ctx.bumps)
+    // unlock_at optionally locks withdrawals until that unix timestamp
+    pub fn initialize(ctx: Context<Initialize>, unlock_at: Option<i64>) -> Result<()> {
+        ctx.accounts.initialize(unlock_at, &ctx.bumps)
+    }
+
+    // Locks the vault until a later unix timestamp
+    // A lock can only be pushed back, never brought forward or removed
+    pub fn extend_lock(ctx: Context<UpdateVault>, unlock_at: i64) -> Result<()> {
+        ctx.accounts.extend_lock(unlock_at)
+    }
+
+    // Limits SOL withdrawals to `limit` lamports in any rolling `period_seconds` (a limit of 0 removes it)
+    // A tighter limit applies at once; a looser one only after LIMIT_INCREASE_DELAY
+    pub fn set_withdraw_limit(
+        ctx: Context<UpdateVault>,
+        limit: u64,
+        period_seconds: i64,
+    ) -> Result<()> {
+        ctx.accounts.set_withdraw_limit(limit, period_seconds)
     }
 
     // Deposits funds from the user's wallet into their vault
@@ -39,10 +82,84 @@ pub mod vault {
         ctx.accounts.withdraw(amount)
     }
 
+    // Deposits SPL or Token-2022 tokens from the user's token account into the vault
+    // Each mint gets its own associated token account owned by the vault PDA,
+    // created on the first deposit of that mint
+    pub fn deposit_token(ctx: Context<TokenPayment>, amount: u64) -> Result<()> {
+        ctx.accounts.deposit_token(amount)
+    }
+
+    // Withdraws tokens from the vault's token account back to the user's
+    // The vault PDA signs the transfer, like it does for SOL withdrawals
+    pub fn withdraw_token(ctx: Context<TokenPayment>, amount: u64) -> Result<()> {
+        ctx.accounts.withdraw_token(amount)
+    }
+
     // Closes the vault completely, transferring any remaining funds to the user
     // This also closes the vault_state account, returning its rent-exempt balance to the user
-    pub fn close_vault(ctx: Context<CloseVault>) -> Result<()> {
-        ctx.accounts.close()
+    // Every delegate must be revoked first
+    // Every token account the vault owns is passed in remaining_accounts as
+    // [vault token account, mint, user token account] and is swept and closed as well
+    // The mint is writable so Token-2022 transfer fees withheld in the vault can be harvested
+    pub fn close_vault<'info>(ctx: Context<'_, '_, 'info, 'info, CloseVault<'info>>) -> Result<()> {
+        ctx.accounts.close(ctx.remaining_accounts)
+    }
+
+    // Lets `delegate` withdraw up to `amount` lamports from the vault until `expiry`
+    // Approving again replaces the allowance and expiry
+    pub fn approve_delegate(
+        ctx: Context<ApproveDelegate>,
+        delegate: Pubkey,
+        amount: u64,
+        expiry: i64,
+    ) -> Result<()> {
+        ctx.accounts.approve_delegate(delegate, amount, expiry, &ctx.bumps)
+    }
+
+    // Revokes a delegate's allowance, closing its account
+    pub fn revoke_delegate(ctx: Context<RevokeDelegate>) -> Result<()> {
+        ctx.accounts.revoke_delegate()
+    }
+
+    // The delegate withdraws part of their allowance from the owner's vault to their own wallet
+    pub fn delegate_withdraw(ctx: Context<DelegateWithdraw>, amount: u64) -> Result<()> {
+        ctx.accounts.delegate_withdraw(amount)
+    }
+
+    // Creates a multisig vault shared by `members`, any `threshold` of whom can move funds
+    // Anyone can deposit by sending SOL to the multisig's vault PDA
+    pub fn create_multisig(
+        ctx: Context<CreateMultisig>,
+        seed: u64,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        ctx.accounts.create_multisig(seed, members, threshold, &ctx.bumps)
+    }
+
+    // A member proposes a withdrawal or a change to the members or threshold
+    // The proposer's approval is counted right away
+    pub fn propose(ctx: Context<Propose>, action: ProposalAction) -> Result<()> {
+        ctx.accounts.propose(action, &ctx.bumps)
+    }
+
+    // A member approves an open proposal
+    pub fn approve(ctx: Context<Approve>) -> Result<()> {
+        ctx.accounts.approve()
+    }
+
+    // Anyone executes a proposal once enough members have approved it, as long as the
+    // multisig's members and threshold have not changed since it was opened
+    // The proposal account is closed and its rent returned to the proposer
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        ctx.accounts.execute()
+    }
+
+    // Closes a proposal without executing it, returning its rent to the proposer
+    // The proposer can cancel their own proposal at any time, and anyone can clean up a
+    // proposal gone stale after a member or threshold change
+    pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
+        ctx.accounts.cancel()
     }
 }
 
@@ -63,7 +180,7 @@ pub struct Initialize<'info> {
     // This account is:
     // - Initialized ('init') and paid for by the user
     // - Derived using seeds 'state' and the user's public key
-    // - Given space according to VaultState::INIT_SPACE (10 bytes)
+    // - Given space according to VaultState::INIT_SPACE (80 bytes)
     #[account(
         init,
         payer = user,
@@ -91,9 +208,11 @@ pub struct Initialize<'info> {
 impl<'info> Initialize<'info> {
     // Stores the bump seeds for both PDAs in the vault_state account
     // These are needed later for withdrawals and closing to reconstruct the PDA signatures
-    pub fn initialize(&mut self, bumps: &InitializeBumps) -> Result<()> {
+    // Also stores the optional time lock on withdrawals
+    pub fn initialize(&mut self, unlock_at: Option<i64>, bumps: &InitializeBumps) -> Result<()> {
         self.vault_state.vault_bump = bumps.vault;
         self.vault_state.state_bump = bumps.vault_state;
+        self.vault_state.unlock_at = unlock_at;
 
         Ok(())
     }
@@ -107,14 +226,102 @@ pub struct VaultState {
     pub vault_bump: u8, // u8 = 8 bits = 1 byte
     // Bump seed for the state PDA - needed to validate the correct state account
     pub state_bump: u8,
+    // Unix timestamp before which nothing can be withdrawn, if the vault is time-locked
+    pub unlock_at: Option<i64>, // 1 byte for the Option tag + 8 bytes for the i64
+    // Most lamports that can be withdrawn per period, 0 when withdrawals are not limited
+    // Only SOL withdrawals by the owner and their delegates count: withdraw_token moves
+    // tokens, not lamports, and multisig vaults are separate accounts with their own approvals
+    pub withdraw_limit: u64,
+    // Length of a withdrawal period in seconds
+    pub period_seconds: i64,
+    // Unix timestamp up to which withdrawn_this_period has been decayed
+    pub period_start: i64,
+    // Lamports withdrawn within the rolling window, paid back linearly over period_seconds
+    pub withdrawn_this_period: u64,
+    // A looser limit waiting out LIMIT_INCREASE_DELAY before it replaces the current one
+    pub pending_limit: Option<PendingLimit>, // 1 byte for the Option tag + 24 bytes
+    // Delegation accounts currently open for this vault, which must be revoked before closing
+    pub delegations: u32,
+}
+
+// A withdrawal limit that takes effect at a later time
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PendingLimit {
+    pub limit: u64,          // New lamports per period, 0 to remove the limit
+    pub period_seconds: i64, // New period length in seconds
+    pub effective_at: i64,   // Unix timestamp from which the new limit applies
+}
+
+impl VaultState {
+    // Rejects withdrawals and closing while the time lock has not passed
+    pub fn check_unlocked(&self) -> Result<()> {
+        if let Some(unlock_at) = self.unlock_at {
+            require!(
+                Clock::get()?.unix_timestamp >= unlock_at,
+                VaultError::VaultLocked
+            );
+        }
+        Ok(())
+    }
+
+    // Switches to the pending limit once its delay has passed
+    pub fn apply_pending_limit(&mut self) -> Result<()> {
+        if let Some(pending) = self.pending_limit {
+            if Clock::get()?.unix_timestamp >= pending.effective_at {
+                self.withdraw_limit = pending.limit;
+                self.period_seconds = pending.period_seconds;
+                self.pending_limit = None;
+            }
+        }
+        Ok(())
+    }
+
+    // Counts a SOL withdrawal against a rolling window of period_seconds. What was withdrawn
+    // before is paid back linearly, limit / period_seconds lamports per second, so no
+    // window of that length ever lets out more than the limit
+    pub fn record_withdrawal(&mut self, amount: u64) -> Result<()> {
+        self.apply_pending_limit()?;
+        let now = Clock::get()?.unix_timestamp;
+
+        if self.withdraw_limit == 0 {
+            return Ok(());
+        }
+
+        // Decay the window up to now. Unless it emptied, period_start only moves forward by
+        // the time the decayed lamports account for, rounded up so nothing is paid back twice
+        let elapsed = now.saturating_sub(self.period_start).max(0) as u128;
+        let limit = self.withdraw_limit as u128;
+        let period = self.period_seconds as u128;
+        let decayed = (limit * elapsed / period).min(self.withdrawn_this_period as u128) as u64;
+        if decayed == self.withdrawn_this_period {
+            self.period_start = now;
+        } else {
+            self.period_start += (decayed as u128 * period).div_ceil(limit) as i64;
+        }
+        self.withdrawn_this_period -= decayed;
+
+        let withdrawn = self
+            .withdrawn_this_period
+            .checked_add(amount)
+            .ok_or(VaultError::WithdrawLimitExceeded)?;
+        require!(
+            withdrawn <= self.withdraw_limit,
+            VaultError::WithdrawLimitExceeded
+        );
+        self.withdrawn_this_period = withdrawn;
+
+        Ok(())
+    }
 }
 
 // Space calculation for rent determination
 // Tells Anchor how much space the vault_state account needs
 impl Space for VaultState {
     // The first 8 bytes are for the "Discriminator" (automatic Anchor account type identifier)
-    // 1 byte for vault_bump, 1 byte for state_bump
-    const INIT_SPACE: usize = 8 + 1 + 1;
+    // 1 byte for vault_bump, 1 byte for state_bump, 9 bytes for unlock_at,
+    // 32 bytes for the limit, period and counter, 25 bytes for pending_limit,
+    // 4 bytes for the delegation count
+    const INIT_SPACE: usize = 8 + 1 + 1 + 9 + 32 + 25 + 4;
 }
 
 // ------------------------------------------------------------------------------------------
@@ -130,9 +337,10 @@ pub struct Payment<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
 
-    // The vault_state PDA - not marked 'mut' since data isn't changing during payments
-    // We use this to verify ownership and get the bump seeds for the vault
+    // The vault_state PDA - marked 'mut' because withdrawals count against the limit
+    // We also use this to verify ownership and get the bump seeds for the vault
     #[account(
+        mut,
         seeds = [b"state", user.key().as_ref()],
         bump = vault_state.state_bump,
     )]
@@ -183,6 +391,11 @@ impl<'info> Payment<'info> {
 impl<'info> Payment<'info> {
     // Withdraws SOL from the vault back to the user's wallet
     pub fn withdraw(&mut self, amount: u64) -> Result<()> {
+        // Nothing leaves a time-locked vault before its unlock time
+        self.vault_state.check_unlocked()?;
+        // Treasury vaults only release up to their limit per period
+        self.vault_state.record_withdrawal(amount)?;
+
         // Get references to the System Program
         let cpi_program = self.system_program.to_account_info();
 
@@ -240,11 +453,39 @@ pub struct CloseVault<'info> {
 
     // Required for transferring SOL
     pub system_program: Program<'info, System>,
+
+    // Required for sweeping token accounts, whichever token program each one belongs to
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
 }
 
 impl<'info> CloseVault<'info> {
     // Closes the vault, returning all funds to the user
-    pub fn close(&mut self) -> Result<()> {
+    // - token_accounts: Groups of [vault token account, mint, user token account] to sweep
+    pub fn close(&mut self, token_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        // Delegations would otherwise outlive the vault and apply to the next one at this address
+        require!(
+            self.vault_state.delegations == 0,
+            VaultError::DelegationsOpen
+        );
+        // A time-locked vault can't be emptied by closing it either
+        self.vault_state.check_unlocked()?;
+        // Nor can a rate-limited one; the limit has to be removed first, which is delayed
+        self.vault_state.apply_pending_limit()?;
+        require!(
+            self.vault_state.withdraw_limit == 0 && self.vault_state.pending_limit.is_none(),
+            VaultError::WithdrawLimitSet
+        );
+
+        // Sweep the token accounts first, since they need the vault PDA to sign
+        require!(
+            token_accounts.chunks_exact(3).remainder().is_empty(),
+            VaultError::InvalidTokenAccounts
+        );
+        for accounts in token_accounts.chunks(3) {
+            self.sweep_token_account(accounts)?;
+        }
+
         // Get the current balance of the vault
         let vault_balance = self.vault.to_account_info().lamports();
 
@@ -278,4 +519,946 @@ impl<'info> CloseVault<'info> {
 
         Ok(())
     }
+
+    // Moves a vault token account's balance to the user and closes it, rent going to the user
+    // A Token-2022 account still holding withheld transfer fees can't be closed, so they are
+    // harvested to the mint first
+    fn sweep_token_account(&self, accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        let [vault_ata, mint, user_ata] = accounts else {
+            return err!(VaultError::InvalidTokenAccounts);
+        };
+
+        // Pick the token program that owns this account
+        let token_program = if vault_ata.owner == &self.token_program.key() {
+            self.token_program.to_account_info()
+        } else if vault_ata.owner == &self.token_2022_program.key() {
+            self.token_2022_program.to_account_info()
+        } else {
+            return err!(VaultError::InvalidTokenAccounts);
+        };
+
+        let vault_account = InterfaceAccount::<TokenAccount>::try_from(vault_ata)?;
+        let user_account = InterfaceAccount::<TokenAccount>::try_from(user_ata)?;
+        let mint_account = InterfaceAccount::<Mint>::try_from(mint)?;
+        require!(
+            vault_account.owner == self.vault.key()
+                && vault_account.mint == mint.key()
+                && user_account.owner == self.user.key()
+                && user_account.mint == mint.key(),
+            VaultError::InvalidTokenAccounts
+        );
+
+        let key_ref = self.user.key();
+        let vault_bump = self.vault_state.vault_bump;
+        let seeds = &[b"vault", key_ref.as_ref(), &[vault_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        if vault_account.amount > 0 {
+            let cpi_accounts = TransferChecked {
+                from: vault_ata.clone(),
+                mint: mint.clone(),
+                to: user_ata.clone(),
+                authority: self.vault.to_account_info(),
+            };
+            let cpi_ctx =
+                CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer_seeds);
+            transfer_checked(cpi_ctx, vault_account.amount, mint_account.decimals)?;
+        }
+
+        harvest_withheld_fees(token_program.clone(), mint.clone(), vault_ata.clone())?;
+
+        let cpi_accounts = CloseAccount {
+            account: vault_ata.clone(),
+            destination: self.user.to_account_info(),
+            authority: self.vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(token_program, cpi_accounts, signer_seeds);
+        close_account(cpi_ctx)
+    }
+}
+
+// Moves any transfer fees withheld in a Token-2022 `account` to the mint, so the account
+// can be closed. Harvesting is permissionless, but the mint must be passed as writable.
+fn harvest_withheld_fees<'info>(
+    token_program: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+) -> Result<()> {
+    if *account.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+
+    let withheld = {
+        let data = account.try_borrow_data()?;
+        let state = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+        match state.get_extension::<TransferFeeAmount>() {
+            Ok(fee_amount) => u64::from(fee_amount.withheld_amount),
+            Err(_) => 0,
+        }
+    };
+    if withheld == 0 {
+        return Ok(());
+    }
+
+    let cpi_ctx = CpiContext::new(
+        token_program.clone(),
+        HarvestWithheldTokensToMint {
+            token_program_id: token_program,
+            mint,
+        },
+    );
+    harvest_withheld_tokens_to_mint(cpi_ctx, vec![account])
+}
+
+// ------------------------------------------------------------------------------------------
+// VAULT SETTINGS
+// ------------------------------------------------------------------------------------------
+
+// UpdateVault accounts - Used for extend_lock and set_withdraw_limit
+#[derive(Accounts)]
+pub struct UpdateVault<'info> {
+    // User account - must sign the transaction
+    pub user: Signer<'info>,
+
+    // The vault_state PDA - marked 'mut' because the settings change
+    #[account(
+        mut,
+        seeds = [b"state", user.key().as_ref()],
+        bump = vault_state.state_bump,
+    )]
+    pub vault_state: Account<'info, VaultState>,
+}
+
+impl<'info> UpdateVault<'info> {
+    // Sets a new unlock time, which must be later than the current one if there is one
+    pub fn extend_lock(&mut self, unlock_at: i64) -> Result<()> {
+        if let Some(current) = self.vault_state.unlock_at {
+            require!(unlock_at > current, VaultError::LockShortened);
+        }
+        self.vault_state.unlock_at = Some(unlock_at);
+
+        Ok(())
+    }
+
+    // Sets the withdrawal limit, delaying it if it would let more out than the current one
+    pub fn set_withdraw_limit(&mut self, limit: u64, period_seconds: i64) -> Result<()> {
+        require!(
+            limit == 0 || period_seconds > 0,
+            VaultError::InvalidWithdrawLimit
+        );
+
+        // A pending limit whose delay has passed is the current one, so compare against it
+        self.vault_state.apply_pending_limit()?;
+
+        let state = &mut self.vault_state;
+        // Looser means no limit where there was one, a higher limit or a shorter period
+        let looser = state.withdraw_limit != 0
+            && (limit == 0
+                || limit > state.withdraw_limit
+                || period_seconds < state.period_seconds);
+
+        if looser {
+            state.pending_limit = Some(PendingLimit {
+                limit,
+                period_seconds,
+                effective_at: Clock::get()?
+                    .unix_timestamp
+                    .saturating_add(LIMIT_INCREASE_DELAY),
+            });
+        } else {
+            // A tighter limit also cancels any looser one still waiting
+            state.withdraw_limit = limit;
+            state.period_seconds = period_seconds;
+            state.pending_limit = None;
+        }
+
+        Ok(())
+    }
+}
+
+// ------------------------------------------------------------------------------------------
+// TOKEN OPERATIONS
+// ------------------------------------------------------------------------------------------
+
+// TokenPayment accounts - Used for both deposit_token and withdraw_token
+// Works with SPL Token and Token-2022 mints through the token interface
+#[derive(Accounts)]
+pub struct TokenPayment<'info> {
+    // User account - must sign the transaction
+    // 'mut' because they pay for any token account created on demand
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    // The vault_state PDA - used to verify ownership and get the vault bump
+    #[account(
+        seeds = [b"state", user.key().as_ref()],
+        bump = vault_state.state_bump,
+    )]
+    pub vault_state: Account<'info, VaultState>,
+
+    // The vault PDA - owns the vault's token accounts and signs withdrawals
+    #[account(
+        seeds = [b"vault", user.key().as_ref()],
+        bump = vault_state.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    // The mint of the token being moved
+    #[account(
+        mint::token_program = token_program
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    // The user's token account for this mint
+    // Created on demand so a withdrawal works even if the user closed it
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_ata: InterfaceAccount<'info, TokenAccount>,
+
+    // The vault's token account for this mint, owned by the vault PDA
+    // Created on the first deposit of the mint and closed by close_vault
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint,
+        associated_token::authority = vault,
+        associated_token::token_program = token_program
+    )]
+    pub vault_ata: InterfaceAccount<'info, TokenAccount>,
+
+    // Required for creating the token accounts and moving the tokens
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TokenPayment<'info> {
+    // Deposits tokens from the user's token account to the vault's
+    // Any Token-2022 transfer fee is withheld from what the vault receives
+    pub fn deposit_token(&mut self, amount: u64) -> Result<()> {
+        let cpi_accounts = TransferChecked {
+            from: self.user_ata.to_account_info(), // Source: user's token account
+            mint: self.mint.to_account_info(),     // Mint, checked against the decimals
+            to: self.vault_ata.to_account_info(),  // Destination: vault's token account
+            authority: self.user.to_account_info(), // The user signs for their own tokens
+        };
+
+        // No signer seeds needed because the user is the signer
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)
+    }
+
+    // Withdraws tokens from the vault's token account back to the user's
+    pub fn withdraw_token(&mut self, amount: u64) -> Result<()> {
+        self.vault_state.check_unlocked()?;
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault_ata.to_account_info(), // Source: vault's token account
+            mint: self.mint.to_account_info(),      // Mint, checked against the decimals
+            to: self.user_ata.to_account_info(),    // Destination: user's token account
+            authority: self.vault.to_account_info(), // The vault PDA owns the tokens
+        };
+
+        // The vault PDA must "sign" this transfer since it owns the token account
+        let key_ref = self.user.key();
+        let vault_bump = self.vault_state.vault_bump;
+        let seeds = &[b"vault", key_ref.as_ref(), &[vault_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)
+    }
+}
+
+// ------------------------------------------------------------------------------------------
+// DELEGATES
+// ------------------------------------------------------------------------------------------
+// A delegate, e.g. a hot wallet, can withdraw SOL from a vault up to an allowance without
+// the owner's key. Each allowance lives in its own PDA seeded by the vault and the delegate,
+// with the owner stored first so clients can list all of a vault's delegates with a memcmp
+// filter at offset 8. The vault counts its open delegations and can't be closed until every
+// one is revoked, so no allowance carries over to a vault re-initialized at the same address.
+
+// Struct defining the data stored in a delegation account
+#[account]
+pub struct Delegation {
+    pub owner: Pubkey,    // The vault owner who approved the delegate
+    pub delegate: Pubkey, // Wallet allowed to withdraw
+    pub allowance: u64,   // Lamports the delegate can still withdraw
+    pub expiry: i64,      // Unix timestamp after which the allowance can't be used
+    pub bump: u8,         // Bump seed for the delegation PDA
+}
+
+impl Space for Delegation {
+    // Discriminator, owner, delegate, allowance, expiry and bump
+    const INIT_SPACE: usize = 8 + 32 + 32 + 8 + 8 + 1;
+}
+
+// ApproveDelegate accounts - Used for the approve_delegate instruction
+#[derive(Accounts)]
+#[instruction(delegate: Pubkey)]
+pub struct ApproveDelegate<'info> {
+    // The vault owner - pays for the delegation account
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    // The vault_state - marked 'mut' because a new delegation is counted on it
+    #[account(
+        mut,
+        seeds = [b"state", user.key().as_ref()],
+        bump = vault_state.state_bump,
+    )]
+    pub vault_state: Account<'info, VaultState>,
+
+    #[account(
+        seeds = [b"vault", user.key().as_ref()],
+        bump = vault_state.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    // Created on the first approval, overwritten by later ones
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"delegate", vault.key().as_ref(), delegate.as_ref()],
+        bump,
+        space = Delegation::INIT_SPACE
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ApproveDelegate<'info> {
+    pub fn approve_delegate(
+        &mut self,
+        delegate: Pubkey,
+        amount: u64,
+        expiry: i64,
+        bumps: &ApproveDelegateBumps,
+    ) -> Result<()> {
+        require!(
+            expiry > Clock::get()?.unix_timestamp,
+            VaultError::InvalidExpiry
+        );
+
+        // A freshly created delegation is still zeroed, re-approvals already have an owner
+        if self.delegation.owner == Pubkey::default() {
+            self.vault_state.delegations = self
+                .vault_state
+                .delegations
+                .checked_add(1)
+                .ok_or(VaultError::Overflow)?;
+        }
+        self.delegation.set_inner(Delegation {
+            owner: self.user.key(),
+            delegate,
+            allowance: amount,
+            expiry,
+            bump: bumps.delegation,
+        });
+
+        Ok(())
+    }
+}
+
+// RevokeDelegate accounts - Used for the revoke_delegate instruction
+#[derive(Accounts)]
+pub struct RevokeDelegate<'info> {
+    // The vault owner - gets the delegation account's rent back
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    // The vault_state - marked 'mut' because the delegation is no longer counted on it
+    #[account(
+        mut,
+        seeds = [b"state", user.key().as_ref()],
+        bump = vault_state.state_bump,
+    )]
+    pub vault_state: Account<'info, VaultState>,
+
+    #[account(
+        seeds = [b"vault", user.key().as_ref()],
+        bump = vault_state.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    // The 'close = user' constraint closes the delegation, ending the allowance
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"delegate", vault.key().as_ref(), delegation.delegate.as_ref()],
+        bump = delegation.bump,
+    )]
+    pub delegation: Account<'info, Delegation>,
+}
+
+impl<'info> RevokeDelegate<'info> {
+    pub fn revoke_delegate(&mut self) -> Result<()> {
+        // The delegation account is closed by the close = user constraint
+        self.vault_state.delegations = self
+            .vault_state
+            .delegations
+            .checked_sub(1)
+            .ok_or(VaultError::Overflow)?;
+        Ok(())
+    }
+}
+
+// DelegateWithdraw accounts - Used for the delegate_withdraw instruction
+#[derive(Accounts)]
+pub struct DelegateWithdraw<'info> {
+    // The delegate - must sign and receives the lamports
+    #[account(mut)]
+    pub delegate: Signer<'info>,
+
+    // The vault owner (not a signer in this transaction)
+    pub owner: SystemAccount<'info>,
+
+    // The owner's vault_state - marked 'mut' because delegate withdrawals count against the limit
+    #[account(
+        mut,
+        seeds = [b"state", owner.key().as_ref()],
+        bump = vault_state.state_bump,
+    )]
+    pub vault_state: Account<'info, VaultState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", owner.key().as_ref()],
+        bump = vault_state.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    // The delegation - marked 'mut' because the allowance goes down
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = delegate,
+        seeds = [b"delegate", vault.key().as_ref(), delegate.key().as_ref()],
+        bump = delegation.bump,
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> DelegateWithdraw<'info> {
+    // Withdraws SOL to the delegate, spending their allowance
+    // The vault's time lock and withdrawal limit apply as they do to the owner
+    pub fn delegate_withdraw(&mut self, amount: u64) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp < self.delegation.expiry,
+            VaultError::DelegationExpired
+        );
+        self.delegation.allowance = self
+            .delegation
+            .allowance
+            .checked_sub(amount)
+            .ok_or(VaultError::AllowanceExceeded)?;
+        self.vault_state.check_unlocked()?;
+        self.vault_state.record_withdrawal(amount)?;
+
+        let cpi_accounts = Transfer {
+            from: self.vault.to_account_info(),
+            to: self.delegate.to_account_info(),
+        };
+
+        // The vault PDA signs with the owner's seeds
+        let key_ref = self.owner.key();
+        let vault_bump = self.vault_state.vault_bump;
+        let seeds = &[b"vault", key_ref.as_ref(), &[vault_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        transfer(cpi_ctx, amount)
+    }
+}
+
+// ------------------------------------------------------------------------------------------
+// MULTISIG VAULTS
+// ------------------------------------------------------------------------------------------
+// A multisig vault holds SOL in a PDA derived from the multisig account instead of a user,
+// so no single key can move it. Every change goes through a proposal that needs `threshold`
+// approvals from current members before anyone can execute it. Changing the members or the
+// threshold invalidates every proposal still open, so approvals never carry over.
+
+// Struct defining the data stored in a multisig account
+#[account]
+pub struct Multisig {
+    pub creator: Pubkey, // Wallet that created the multisig, part of the PDA seeds
+    pub seed: u64,       // Lets one creator set up several multisigs
+    pub members: Vec<Pubkey>, // Wallets that can propose and approve, at most MAX_MEMBERS
+    pub threshold: u8,   // Approvals needed to execute a proposal
+    pub proposal_count: u64, // Index of the next proposal, part of its PDA seeds
+    pub members_version: u64, // Bumped on every member or threshold change
+    pub vault_bump: u8,  // Bump seed for the multisig vault PDA
+    pub bump: u8,        // Bump seed for the multisig PDA
+}
+
+impl Multisig {
+    // Checks a member list and threshold are usable together
+    // Members must be unique and the threshold reachable
+    pub fn validate(members: &[Pubkey], threshold: u8) -> Result<()> {
+        require!(
+            !members.is_empty() && members.len() <= MAX_MEMBERS,
+            VaultError::InvalidMembers
+        );
+        for (i, member) in members.iter().enumerate() {
+            require!(!members[..i].contains(member), VaultError::InvalidMembers);
+        }
+        require!(
+            threshold > 0 && threshold as usize <= members.len(),
+            VaultError::InvalidThreshold
+        );
+        Ok(())
+    }
+
+    // Rejects signers that are not currently members
+    pub fn check_member(&self, key: &Pubkey) -> Result<()> {
+        require!(self.members.contains(key), VaultError::NotAMember);
+        Ok(())
+    }
+
+    // Rejects proposals opened before the last member or threshold change, whose approvals
+    // were gathered under a different member set
+    pub fn check_version(&self, proposal: &Proposal) -> Result<()> {
+        require!(
+            proposal.members_version == self.members_version,
+            VaultError::StaleProposal
+        );
+        Ok(())
+    }
+}
+
+impl Space for Multisig {
+    // Discriminator, creator, seed, members (length prefix + MAX_MEMBERS keys),
+    // threshold, proposal_count, members_version and the two bumps
+    const INIT_SPACE: usize = 8 + 32 + 8 + (4 + 32 * MAX_MEMBERS) + 1 + 8 + 8 + 1 + 1;
+}
+
+// What a proposal does once executed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub enum ProposalAction {
+    // Send `amount` lamports from the multisig vault to `to`
+    Withdraw { to: Pubkey, amount: u64 },
+    // Add a member
+    AddMember { member: Pubkey },
+    // Remove a member, as long as the threshold stays reachable
+    RemoveMember { member: Pubkey },
+    // Change the number of approvals needed
+    ChangeThreshold { threshold: u8 },
+}
+
+// Struct defining the data stored in a proposal account
+#[account]
+pub struct Proposal {
+    pub multisig: Pubkey,       // The multisig this proposal belongs to
+    pub index: u64,             // Position among the multisig's proposals, part of the PDA seeds
+    pub proposer: Pubkey,       // Member who opened it, gets the rent back once it is closed
+    pub action: ProposalAction, // What happens on execution
+    pub approvals: Vec<Pubkey>, // Members who approved, at most MAX_MEMBERS
+    pub members_version: u64,   // The multisig's members_version when it was opened
+    pub bump: u8,               // Bump seed for the proposal PDA
+}
+
+impl Space for Proposal {
+    // Discriminator, multisig, index, proposer, action (tag + largest variant),
+    // approvals (length prefix + MAX_MEMBERS keys), members_version and bump
+    const INIT_SPACE: usize = 8 + 32 + 8 + 32 + (1 + 40) + (4 + 32 * MAX_MEMBERS) + 8 + 1;
+}
+
+// CreateMultisig accounts - Used for the create_multisig instruction
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct CreateMultisig<'info> {
+    // The creator - pays for the multisig account, but needs not be a member
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"multisig", creator.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+        space = Multisig::INIT_SPACE
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    // The multisig's vault PDA - holds the SOL, like the single-user vault
+    #[account(
+        seeds = [b"multisig_vault", multisig.key().as_ref()],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateMultisig<'info> {
+    pub fn create_multisig(
+        &mut self,
+        seed: u64,
+        members: Vec<Pubkey>,
+        threshold: u8,
+        bumps: &CreateMultisigBumps,
+    ) -> Result<()> {
+        Multisig::validate(&members, threshold)?;
+
+        self.multisig.set_inner(Multisig {
+            creator: self.creator.key(),
+            seed,
+            members,
+            threshold,
+            proposal_count: 0,
+            members_version: 0,
+            vault_bump: bumps.vault,
+            bump: bumps.multisig,
+        });
+
+        Ok(())
+    }
+}
+
+// Propose accounts - Used for the propose instruction
+#[derive(Accounts)]
+pub struct Propose<'info> {
+    // The member opening the proposal - pays for the proposal account
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    // The multisig - marked 'mut' because the proposal count goes up
+    #[account(
+        mut,
+        seeds = [b"multisig", multisig.creator.as_ref(), multisig.seed.to_le_bytes().as_ref()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        init,
+        payer = proposer,
+        seeds = [
+            b"proposal",
+            multisig.key().as_ref(),
+            multisig.proposal_count.to_le_bytes().as_ref()
+        ],
+        bump,
+        space = Proposal::INIT_SPACE
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Propose<'info> {
+    pub fn propose(&mut self, action: ProposalAction, bumps: &ProposeBumps) -> Result<()> {
+        self.multisig.check_member(&self.proposer.key())?;
+
+        self.proposal.set_inner(Proposal {
+            multisig: self.multisig.key(),
+            index: self.multisig.proposal_count,
+            proposer: self.proposer.key(),
+            action,
+            approvals: vec![self.proposer.key()],
+            members_version: self.multisig.members_version,
+            bump: bumps.proposal,
+        });
+        self.multisig.proposal_count += 1;
+
+        Ok(())
+    }
+}
+
+// Approve accounts - Used for the approve instruction
+#[derive(Accounts)]
+pub struct Approve<'info> {
+    pub member: Signer<'info>,
+
+    #[account(
+        seeds = [b"multisig", multisig.creator.as_ref(), multisig.seed.to_le_bytes().as_ref()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    // The proposal - marked 'mut' because the approval is recorded on it
+    #[account(
+        mut,
+        has_one = multisig,
+        seeds = [b"proposal", multisig.key().as_ref(), proposal.index.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
+
+impl<'info> Approve<'info> {
+    pub fn approve(&mut self) -> Result<()> {
+        let member = self.member.key();
+        self.multisig.check_member(&member)?;
+        self.multisig.check_version(&self.proposal)?;
+        require!(
+            !self.proposal.approvals.contains(&member),
+            VaultError::AlreadyApproved
+        );
+
+        self.proposal.approvals.push(member);
+
+        Ok(())
+    }
+}
+
+// ExecuteProposal accounts - Used for the execute_proposal instruction
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    // Anyone can execute once the threshold is met
+    pub executor: Signer<'info>,
+
+    // The multisig - marked 'mut' because member and threshold changes are written to it
+    #[account(
+        mut,
+        seeds = [b"multisig", multisig.creator.as_ref(), multisig.seed.to_le_bytes().as_ref()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    // The proposal - closed on execution so it can't run twice
+    #[account(
+        mut,
+        has_one = multisig,
+        has_one = proposer,
+        close = proposer,
+        seeds = [b"proposal", multisig.key().as_ref(), proposal.index.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    // The member who opened the proposal - gets its rent back
+    #[account(mut)]
+    pub proposer: SystemAccount<'info>,
+
+    // The multisig's vault PDA - marked 'mut' because withdrawals come out of it
+    #[account(
+        mut,
+        seeds = [b"multisig_vault", multisig.key().as_ref()],
+        bump = multisig.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    // Where a withdrawal goes, left out for member and threshold changes
+    #[account(mut)]
+    pub recipient: Option<SystemAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ExecuteProposal<'info> {
+    pub fn execute(&mut self) -> Result<()> {
+        // Approvals only count under the member set they were given for
+        self.multisig.check_version(&self.proposal)?;
+        require!(
+            self.proposal.approvals.len() >= self.multisig.threshold as usize,
+            VaultError::ThresholdNotMet
+        );
+
+        match self.proposal.action {
+            ProposalAction::Withdraw { to, amount } => self.withdraw(to, amount),
+            ProposalAction::AddMember { member } => {
+                let mut members = self.multisig.members.clone();
+                members.push(member);
+                Multisig::validate(&members, self.multisig.threshold)?;
+                self.multisig.members = members;
+                self.bump_members_version()
+            }
+            ProposalAction::RemoveMember { member } => {
+                let mut members = self.multisig.members.clone();
+                members.retain(|m| *m != member);
+                require!(
+                    members.len() < self.multisig.members.len(),
+                    VaultError::NotAMember
+                );
+                Multisig::validate(&members, self.multisig.threshold)?;
+                self.multisig.members = members;
+                self.bump_members_version()
+            }
+            ProposalAction::ChangeThreshold { threshold } => {
+                Multisig::validate(&self.multisig.members, threshold)?;
+                self.multisig.threshold = threshold;
+                self.bump_members_version()
+            }
+        }
+    }
+
+    // Invalidates every other open proposal, so none runs on approvals from the old member set
+    fn bump_members_version(&mut self) -> Result<()> {
+        self.multisig.members_version += 1;
+        Ok(())
+    }
+
+    // Sends SOL from the multisig vault, signed with the vault PDA seeds
+    fn withdraw(&self, to: Pubkey, amount: u64) -> Result<()> {
+        let recipient = self
+            .recipient
+            .as_ref()
+            .ok_or(VaultError::InvalidRecipient)?;
+        require_keys_eq!(recipient.key(), to, VaultError::InvalidRecipient);
+
+        let cpi_accounts = Transfer {
+            from: self.vault.to_account_info(),
+            to: recipient.to_account_info(),
+        };
+
+        let multisig_key = self.multisig.key();
+        let seeds = &[
+            b"multisig_vault",
+            multisig_key.as_ref(),
+            &[self.multisig.vault_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        transfer(cpi_ctx, amount)
+    }
+}
+
+// CancelProposal accounts - Used for the cancel_proposal instruction
+#[derive(Accounts)]
+pub struct CancelProposal<'info> {
+    // The proposer, or anyone once the proposal is stale
+    pub closer: Signer<'info>,
+
+    #[account(
+        seeds = [b"multisig", multisig.creator.as_ref(), multisig.seed.to_le_bytes().as_ref()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    // The proposal - closed without running its action
+    #[account(
+        mut,
+        has_one = multisig,
+        has_one = proposer,
+        close = proposer,
+        seeds = [b"proposal", multisig.key().as_ref(), proposal.index.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    // The member who opened the proposal - gets its rent back
+    #[account(mut)]
+    pub proposer: SystemAccount<'info>,
+}
+
+impl<'info> CancelProposal<'info> {
+    pub fn cancel(&mut self) -> Result<()> {
+        // A stale proposal can never execute, so there is nothing left to protect
+        require!(
+            self.closer.key() == self.proposal.proposer
+                || self.multisig.check_version(&self.proposal).is_err(),
+            VaultError::ProposalStillOpen
+        );
+
+        Ok(())
+    }
+}
+
+// ------------------------------------------------------------------------------------------
+// ERRORS
+// ------------------------------------------------------------------------------------------
+
+#[error_code]
+pub enum VaultError {
+    // close_vault's remaining_accounts are not [vault token account, mint, user token account] groups
+    #[msg("Token accounts must be vault account, mint and user account groups for the same mint")]
+    InvalidTokenAccounts,
+
+    // A withdrawal or close was attempted before the vault's unlock time
+    #[msg("The vault is time-locked until its unlock time")]
+    VaultLocked,
+
+    // extend_lock was given an unlock time that is not later than the current one
+    #[msg("A time lock can only be extended, never shortened")]
+    LockShortened,
+
+    // A SOL withdrawal would take more than the vault's limit for the current period
+    #[msg("Withdrawal exceeds the vault's limit for this period")]
+    WithdrawLimitExceeded,
+
+    // A limit was set without a usable period
+    #[msg("A withdrawal limit needs a period of at least one second")]
+    InvalidWithdrawLimit,
+
+    // close_vault was called on a vault that still has a withdrawal limit
+    #[msg("Remove the withdrawal limit before closing the vault")]
+    WithdrawLimitSet,
+
+    // A multisig member list is empty, too long or has duplicates
+    #[msg("Members must be unique and at most MAX_MEMBERS")]
+    InvalidMembers,
+
+    // A multisig threshold is zero or above the number of members
+    #[msg("Threshold must be between 1 and the number of members")]
+    InvalidThreshold,
+
+    // The signer, or the member to remove, is not a member of the multisig
+    #[msg("Not a member of this multisig")]
+    NotAMember,
+
+    // A member tried to approve the same proposal twice
+    #[msg("Member already approved this proposal")]
+    AlreadyApproved,
+
+    // execute_proposal was called before enough members approved
+    #[msg("Proposal does not have enough approvals yet")]
+    ThresholdNotMet,
+
+    // A withdrawal proposal was executed without its recipient account
+    #[msg("Recipient does not match the proposal")]
+    InvalidRecipient,
+
+    // A delegate tried to withdraw after their allowance expired
+    #[msg("Delegation has expired")]
+    DelegationExpired,
+
+    // A delegate tried to withdraw more than their remaining allowance
+    #[msg("Withdrawal exceeds the delegate's allowance")]
+    AllowanceExceeded,
+
+    // A proposal was approved or executed after the multisig's members or threshold changed
+    #[msg("Multisig members changed since this proposal was opened")]
+    StaleProposal,
+
+    // close_vault was called while delegations are still open
+    #[msg("Revoke every delegate before closing the vault")]
+    DelegationsOpen,
+
+    // Someone other than the proposer tried to cancel a proposal that can still execute
+    #[msg("Only the proposer can cancel a proposal that is not stale")]
+    ProposalStillOpen,
+
+    // approve_delegate was given an expiry that has already passed
+    #[msg("Delegation expiry must be in the future")]
+    InvalidExpiry,
+
+    // A counter on the vault state went past its bounds
+    #[msg("Arithmetic overflow")]
+    Overflow,
 }
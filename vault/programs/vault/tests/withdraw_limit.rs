@@ -0,0 +1,193 @@
+// Integration tests for the rolling SOL withdrawal limit, which need the clock moved forward
+// and so can't run against the local validator the TypeScript tests use. The compiled
+// vault.so runs inside solana-program-test. They only compile with the test-sbf feature,
+// so build and run them with `cargo test-sbf` (or `anchor run test-sbf`).
+#![cfg(feature = "test-sbf")]
+
+use anchor_lang::{system_program, AccountDeserialize, InstructionData, ToAccountMetas};
+use solana_program_test::{BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    clock::Clock,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::{Transaction, TransactionError},
+};
+use vault::{VaultError, VaultState, LIMIT_INCREASE_DELAY};
+
+const SOL: u64 = 1_000_000_000;
+const PERIOD: i64 = 1_000;
+
+struct Setup {
+    ctx: ProgramTestContext,
+    user: Keypair,
+}
+
+impl Setup {
+    // A user with an initialized vault holding 10 SOL
+    async fn new() -> Self {
+        let ctx = ProgramTest::new("vault", vault::ID, None)
+            .start_with_context()
+            .await;
+        let mut setup = Setup {
+            ctx,
+            user: Keypair::new(),
+        };
+
+        let fund = system_instruction::transfer(&setup.ctx.payer.pubkey(), &setup.user(), 20 * SOL);
+        setup.send(fund).await.unwrap();
+
+        let initialize = Instruction {
+            program_id: vault::ID,
+            accounts: vault::accounts::Initialize {
+                user: setup.user(),
+                vault_state: setup.state(),
+                vault: setup.vault(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: vault::instruction::Initialize { unlock_at: None }.data(),
+        };
+        setup.send(initialize).await.unwrap();
+        setup
+            .payment(vault::instruction::Deposit { amount: 10 * SOL }.data())
+            .await
+            .unwrap();
+
+        setup
+    }
+
+    fn user(&self) -> Pubkey {
+        self.user.pubkey()
+    }
+
+    fn state(&self) -> Pubkey {
+        Pubkey::find_program_address(&[b"state", self.user().as_ref()], &vault::ID).0
+    }
+
+    fn vault(&self) -> Pubkey {
+        Pubkey::find_program_address(&[b"vault", self.user().as_ref()], &vault::ID).0
+    }
+
+    // Sends `ix` signed by the user, with the context payer paying fees
+    async fn send(&mut self, ix: Instruction) -> Result<(), BanksClientError> {
+        let blockhash = self.ctx.get_new_latest_blockhash().await.unwrap();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&self.ctx.payer.pubkey()),
+            &[&self.ctx.payer, &self.user],
+            blockhash,
+        );
+        self.ctx.banks_client.process_transaction(tx).await
+    }
+
+    // Sends deposit or withdraw
+    async fn payment(&mut self, data: Vec<u8>) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: vault::ID,
+            accounts: vault::accounts::Payment {
+                user: self.user(),
+                vault_state: self.state(),
+                vault: self.vault(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data,
+        };
+        self.send(ix).await
+    }
+
+    async fn withdraw(&mut self, amount: u64) -> Result<(), BanksClientError> {
+        self.payment(vault::instruction::Withdraw { amount }.data())
+            .await
+    }
+
+    async fn set_withdraw_limit(&mut self, limit: u64) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: vault::ID,
+            accounts: vault::accounts::UpdateVault {
+                user: self.user(),
+                vault_state: self.state(),
+            }
+            .to_account_metas(None),
+            data: vault::instruction::SetWithdrawLimit {
+                limit,
+                period_seconds: PERIOD,
+            }
+            .data(),
+        };
+        self.send(ix).await
+    }
+
+    async fn vault_state(&mut self) -> VaultState {
+        let account = self
+            .ctx
+            .banks_client
+            .get_account(self.state())
+            .await
+            .unwrap()
+            .unwrap();
+        VaultState::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    // Moves the clock forward by `seconds`
+    async fn advance(&mut self, seconds: i64) {
+        let mut clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp += seconds;
+        self.ctx.set_sysvar(&clock);
+    }
+}
+
+fn custom_error(err: BanksClientError) -> Option<u32> {
+    match err.unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => Some(code),
+        _ => None,
+    }
+}
+
+#[tokio::test]
+async fn withdrawals_decay_over_a_rolling_window() {
+    let mut s = Setup::new().await;
+    s.set_withdraw_limit(SOL).await.unwrap();
+
+    s.withdraw(SOL).await.unwrap();
+    let err = s.withdraw(SOL / 10).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(VaultError::WithdrawLimitExceeded.into())
+    );
+
+    // A quarter of the period pays back a quarter of the limit, not the whole of it
+    s.advance(PERIOD / 4).await;
+    s.withdraw(SOL / 4).await.unwrap();
+    let err = s.withdraw(SOL / 10).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(VaultError::WithdrawLimitExceeded.into())
+    );
+    assert!(s.vault_state().await.withdrawn_this_period > SOL - SOL / 10);
+}
+
+#[tokio::test]
+async fn new_limit_is_compared_against_a_matured_pending_one() {
+    let mut s = Setup::new().await;
+    s.set_withdraw_limit(SOL).await.unwrap();
+
+    // Raising the limit waits out the delay
+    s.set_withdraw_limit(2 * SOL).await.unwrap();
+    let err = s.withdraw(SOL + SOL / 2).await.unwrap_err();
+    assert_eq!(
+        custom_error(err),
+        Some(VaultError::WithdrawLimitExceeded.into())
+    );
+
+    // Once it has matured, 1.5 SOL is tighter than the 2 SOL in force and applies at once
+    s.advance(LIMIT_INCREASE_DELAY).await;
+    s.set_withdraw_limit(SOL + SOL / 2).await.unwrap();
+    let state = s.vault_state().await;
+    assert_eq!(state.withdraw_limit, SOL + SOL / 2);
+    assert!(state.pending_limit.is_none());
+
+    s.withdraw(SOL + SOL / 2).await.unwrap();
+}
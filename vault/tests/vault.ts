@@ -2,6 +2,20 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { Vault } from "../target/types/vault";
 import { PublicKey } from '@solana/web3.js';
+import {
+  TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  ExtensionType,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  createMint,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getMintLen,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from '@solana/spl-token';
 import { expect } from 'chai';
 
 describe("vault", () => {
@@ -26,7 +40,7 @@ describe("vault", () => {
   it("Initializes the vault", async () => {
     // Initialize the vault
     const tx = await vaultProgram.methods
-      .initialize()
+      .initialize(null)
       .accounts({
         user: user.publicKey,
         vaultState: vaultStatePDA,
@@ -169,4 +183,731 @@ describe("vault", () => {
       expect(error.toString()).to.include("Account does not exist");
     }
   })
+
+  it("Limits withdrawals per period", async () => {
+    // Re-open the closed vault and limit it to 0.1 SOL a day
+    await vaultProgram.methods
+      .initialize(null)
+      .accounts({
+        user: user.publicKey,
+        vaultState: vaultStatePDA,
+        vault: vaultPDA,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+    await vaultProgram.methods
+      .setWithdrawLimit(new anchor.BN(100_000_000), new anchor.BN(86_400))
+      .accounts({
+        user: user.publicKey,
+        vaultState: vaultStatePDA,
+      })
+      .rpc();
+
+    await vaultProgram.methods
+      .deposit(new anchor.BN(500_000_000))
+      .accounts({
+        user: user.publicKey,
+        vaultState: vaultStatePDA,
+        vault: vaultPDA,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const withdraw = (amount: number) =>
+      vaultProgram.methods
+        .withdraw(new anchor.BN(amount))
+        .accounts({
+          user: user.publicKey,
+          vaultState: vaultStatePDA,
+          vault: vaultPDA,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+    // Up to the limit goes through, anything past it in the same period does not
+    await withdraw(60_000_000);
+    try {
+      await withdraw(60_000_000);
+      expect.fail("Withdrawal past the limit should fail");
+    } catch (error) {
+      expect(error.toString()).to.include("WithdrawLimitExceeded");
+    }
+
+    // Raising the limit only takes effect after the delay
+    await vaultProgram.methods
+      .setWithdrawLimit(new anchor.BN(1_000_000_000), new anchor.BN(86_400))
+      .accounts({
+        user: user.publicKey,
+        vaultState: vaultStatePDA,
+      })
+      .rpc();
+    const vaultState = await vaultProgram.account.vaultState.fetch(vaultStatePDA);
+    expect(vaultState.withdrawLimit.toNumber()).to.equal(100_000_000);
+    expect(vaultState.pendingLimit.limit.toNumber()).to.equal(1_000_000_000);
+  });
+
+  it("Rejects withdrawals before the unlock time", async () => {
+    // Lock the vault for a day
+    const unlockAt = new anchor.BN(Math.floor(Date.now() / 1000) + 86_400);
+    await vaultProgram.methods
+      .extendLock(unlockAt)
+      .accounts({
+        user: user.publicKey,
+        vaultState: vaultStatePDA,
+      })
+      .rpc();
+
+    // Deposits are still allowed while locked
+    const depositAmount = new anchor.BN(100_000_000);
+    await vaultProgram.methods
+      .deposit(depositAmount)
+      .accounts({
+        user: user.publicKey,
+        vaultState: vaultStatePDA,
+        vault: vaultPDA,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    try {
+      await vaultProgram.methods
+        .withdraw(depositAmount)
+        .accounts({
+          user: user.publicKey,
+          vaultState: vaultStatePDA,
+          vault: vaultPDA,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+      expect.fail("Withdrawal from a locked vault should fail");
+    } catch (error) {
+      expect(error.toString()).to.include("VaultLocked");
+    }
+
+    // The lock can be pushed back but never brought forward
+    try {
+      await vaultProgram.methods
+        .extendLock(unlockAt.subn(1))
+        .accounts({
+          user: user.publicKey,
+          vaultState: vaultStatePDA,
+        })
+        .rpc();
+      expect.fail("Shortening the lock should fail");
+    } catch (error) {
+      expect(error.toString()).to.include("LockShortened");
+    }
+  })
+
+  it("Approves and revokes a delegate", async () => {
+    const delegate = anchor.web3.Keypair.generate();
+    const [delegationPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("delegate"), vaultPDA.toBuffer(), delegate.publicKey.toBuffer()],
+      vaultProgram.programId
+    );
+
+    const allowance = new anchor.BN(50_000_000);
+
+    // An allowance that has already expired could never be used
+    try {
+      await vaultProgram.methods
+        .approveDelegate(delegate.publicKey, allowance, new anchor.BN(Math.floor(Date.now() / 1000) - 60))
+        .accounts({
+          user: user.publicKey,
+          vaultState: vaultStatePDA,
+          vault: vaultPDA,
+          delegation: delegationPDA,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+      expect.fail("Approving an expired delegation should fail");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidExpiry");
+    }
+
+    const expiry = new anchor.BN(Math.floor(Date.now() / 1000) + 3_600);
+    await vaultProgram.methods
+      .approveDelegate(delegate.publicKey, allowance, expiry)
+      .accounts({
+        user: user.publicKey,
+        vaultState: vaultStatePDA,
+        vault: vaultPDA,
+        delegation: delegationPDA,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const delegation = await vaultProgram.account.delegation.fetch(delegationPDA);
+    expect(delegation.owner.toBase58()).to.equal(user.publicKey.toBase58());
+    expect(delegation.allowance.toNumber()).to.equal(allowance.toNumber());
+    expect((await vaultProgram.account.vaultState.fetch(vaultStatePDA)).delegations).to.equal(1);
+
+    // The vault can't be closed while the delegation is open, or it would carry over to the
+    // next vault initialized at the same address
+    try {
+      await vaultProgram.methods.closeVault().accounts({
+        user: user.publicKey,
+        vaultState: vaultStatePDA,
+        vault: vaultPDA,
+        systemProgram: anchor.web3.SystemProgram.programId
+      }).rpc();
+      expect.fail("Closing a vault with an open delegation should fail");
+    } catch (error) {
+      expect(error.toString()).to.include("DelegationsOpen");
+    }
+
+    // The delegate can't go past their allowance
+    try {
+      await vaultProgram.methods
+        .delegateWithdraw(allowance.addn(1))
+        .accounts({
+          delegate: delegate.publicKey,
+          owner: user.publicKey,
+          vaultState: vaultStatePDA,
+          vault: vaultPDA,
+          delegation: delegationPDA,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([delegate])
+        .rpc();
+      expect.fail("Withdrawal past the allowance should fail");
+    } catch (error) {
+      expect(error.toString()).to.include("AllowanceExceeded");
+    }
+
+    await vaultProgram.methods
+      .revokeDelegate()
+      .accounts({
+        user: user.publicKey,
+        vaultState: vaultStatePDA,
+        vault: vaultPDA,
+        delegation: delegationPDA,
+      })
+      .rpc();
+    expect(await provider.connection.getAccountInfo(delegationPDA)).to.be.null;
+    expect((await vaultProgram.account.vaultState.fetch(vaultStatePDA)).delegations).to.equal(0);
+  })
+
+  it("Withdraws from a 2-of-2 multisig vault once both members approve", async () => {
+    const member = anchor.web3.Keypair.generate();
+    const seed = new anchor.BN(1);
+    const [multisigPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("multisig"), user.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+      vaultProgram.programId
+    );
+    const [multisigVaultPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("multisig_vault"), multisigPDA.toBuffer()],
+      vaultProgram.programId
+    );
+    const [proposalPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("proposal"), multisigPDA.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      vaultProgram.programId
+    );
+
+    await vaultProgram.methods
+      .createMultisig(seed, [user.publicKey, member.publicKey], 2)
+      .accounts({
+        creator: user.publicKey,
+        multisig: multisigPDA,
+        vault: multisigVaultPDA,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // Anyone can fund the multisig vault with a plain transfer
+    const amount = 100_000_000;
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.transfer({
+          fromPubkey: user.publicKey,
+          toPubkey: multisigVaultPDA,
+          lamports: amount,
+        })
+      )
+    );
+
+    await vaultProgram.methods
+      .propose({ withdraw: { to: member.publicKey, amount: new anchor.BN(amount) } })
+      .accounts({
+        proposer: user.publicKey,
+        multisig: multisigPDA,
+        proposal: proposalPDA,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const execute = () =>
+      vaultProgram.methods
+        .executeProposal()
+        .accounts({
+          executor: user.publicKey,
+          multisig: multisigPDA,
+          proposal: proposalPDA,
+          proposer: user.publicKey,
+          vault: multisigVaultPDA,
+          recipient: member.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+    // Only the proposer has approved so far
+    try {
+      await execute();
+      expect.fail("Execution below the threshold should fail");
+    } catch (error) {
+      expect(error.toString()).to.include("ThresholdNotMet");
+    }
+
+    await vaultProgram.methods
+      .approve()
+      .accounts({
+        member: member.publicKey,
+        multisig: multisigPDA,
+        proposal: proposalPDA,
+      })
+      .signers([member])
+      .rpc();
+    await execute();
+
+    expect(await provider.connection.getBalance(member.publicKey)).to.equal(amount);
+    expect(await provider.connection.getBalance(proposalPDA)).to.equal(0);
+  })
+
+  it("Removes a member and invalidates proposals opened before the change", async () => {
+    const memberA = anchor.web3.Keypair.generate();
+    const memberB = anchor.web3.Keypair.generate();
+    const seed = new anchor.BN(2);
+    const [multisigPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("multisig"), user.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+      vaultProgram.programId
+    );
+    const [multisigVaultPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("multisig_vault"), multisigPDA.toBuffer()],
+      vaultProgram.programId
+    );
+    const proposalPDA = (index: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("proposal"), multisigPDA.toBuffer(), new anchor.BN(index).toArrayLike(Buffer, "le", 8)],
+        vaultProgram.programId
+      )[0];
+
+    await vaultProgram.methods
+      .createMultisig(seed, [user.publicKey, memberA.publicKey, memberB.publicKey], 2)
+      .accounts({
+        creator: user.publicKey,
+        multisig: multisigPDA,
+        vault: multisigVaultPDA,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const propose = async (action) =>
+      vaultProgram.methods
+        .propose(action)
+        .accounts({
+          proposer: user.publicKey,
+          multisig: multisigPDA,
+          proposal: proposalPDA(
+            (await vaultProgram.account.multisig.fetch(multisigPDA)).proposalCount.toNumber()
+          ),
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    const approve = (index: number, member: anchor.web3.Keypair) =>
+      vaultProgram.methods
+        .approve()
+        .accounts({
+          member: member.publicKey,
+          multisig: multisigPDA,
+          proposal: proposalPDA(index),
+        })
+        .signers([member])
+        .rpc();
+    const execute = (index: number, recipient: PublicKey | null) =>
+      vaultProgram.methods
+        .executeProposal()
+        .accounts({
+          executor: user.publicKey,
+          multisig: multisigPDA,
+          proposal: proposalPDA(index),
+          proposer: user.publicKey,
+          vault: multisigVaultPDA,
+          recipient,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+    // Proposal 0 is left open while proposal 1 removes member B
+    await propose({ withdraw: { to: memberB.publicKey, amount: new anchor.BN(1) } });
+    await propose({ removeMember: { member: memberB.publicKey } });
+    await approve(1, memberA);
+    await execute(1, null);
+
+    const multisig = await vaultProgram.account.multisig.fetch(multisigPDA);
+    expect(multisig.members.map((m) => m.toBase58())).to.deep.equal([
+      user.publicKey.toBase58(),
+      memberA.publicKey.toBase58(),
+    ]);
+    expect(multisig.membersVersion.toNumber()).to.equal(1);
+
+    // The removed member can no longer approve anything
+    try {
+      await approve(0, memberB);
+      expect.fail("A removed member should not be able to approve");
+    } catch (error) {
+      expect(error.toString()).to.include("NotAMember");
+    }
+
+    // Proposal 0 was opened under the old member set, so it can be neither approved nor executed
+    try {
+      await approve(0, memberA);
+      expect.fail("Approving a stale proposal should fail");
+    } catch (error) {
+      expect(error.toString()).to.include("StaleProposal");
+    }
+    try {
+      await execute(0, memberB.publicKey);
+      expect.fail("Executing a stale proposal should fail");
+    } catch (error) {
+      expect(error.toString()).to.include("StaleProposal");
+    }
+
+    const cancel = (index: number, closer: anchor.web3.Keypair) =>
+      vaultProgram.methods
+        .cancelProposal()
+        .accounts({
+          closer: closer.publicKey,
+          multisig: multisigPDA,
+          proposal: proposalPDA(index),
+          proposer: user.publicKey,
+        })
+        .signers([closer])
+        .rpc();
+
+    // Anyone can clean up the stale proposal, and the rent goes back to the proposer
+    const outsider = anchor.web3.Keypair.generate();
+    const proposerBalance = await provider.connection.getBalance(user.publicKey);
+    await cancel(0, outsider);
+    expect(await provider.connection.getAccountInfo(proposalPDA(0))).to.be.null;
+    expect(await provider.connection.getBalance(user.publicKey)).to.be.greaterThan(proposerBalance);
+
+    // A live proposal can only be cancelled by its proposer
+    await propose({ changeThreshold: { threshold: 1 } });
+    try {
+      await cancel(2, memberA);
+      expect.fail("Only the proposer should be able to cancel a live proposal");
+    } catch (error) {
+      expect(error.toString()).to.include("ProposalStillOpen");
+    }
+    await vaultProgram.methods
+      .cancelProposal()
+      .accounts({
+        closer: user.publicKey,
+        multisig: multisigPDA,
+        proposal: proposalPDA(2),
+        proposer: user.publicKey,
+      })
+      .rpc();
+    expect(await provider.connection.getAccountInfo(proposalPDA(2))).to.be.null;
+  })
+
+  describe("token vault", () => {
+    // A fresh owner, since the main vault above is time-locked and rate-limited by now
+    const owner = anchor.web3.Keypair.generate();
+    const [ownerVaultPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), owner.publicKey.toBuffer()],
+      vaultProgram.programId
+    );
+    const [ownerStatePDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("state"), owner.publicKey.toBuffer()],
+      vaultProgram.programId
+    );
+
+    // One mint per token program, each holding a deposit once the first test has run
+    const mints: { mint: PublicKey; tokenProgram: PublicKey }[] = [];
+    const vaultAta = (i: number) =>
+      getAssociatedTokenAddressSync(mints[i].mint, ownerVaultPDA, true, mints[i].tokenProgram);
+    const userAta = (i: number) =>
+      getAssociatedTokenAddressSync(mints[i].mint, owner.publicKey, false, mints[i].tokenProgram);
+    const balance = async (address: PublicKey, i: number) =>
+      Number((await getAccount(provider.connection, address, undefined, mints[i].tokenProgram)).amount);
+
+    const tokenPayment = (i: number) => ({
+      user: owner.publicKey,
+      vaultState: ownerStatePDA,
+      vault: ownerVaultPDA,
+      mint: mints[i].mint,
+      userAta: userAta(i),
+      vaultAta: vaultAta(i),
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      tokenProgram: mints[i].tokenProgram,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    });
+    const closeVault = (groups: PublicKey[][]) =>
+      vaultProgram.methods
+        .closeVault()
+        .accounts({
+          user: owner.publicKey,
+          vaultState: ownerStatePDA,
+          vault: ownerVaultPDA,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          token2022Program: TOKEN_2022_PROGRAM_ID,
+        })
+        .remainingAccounts(
+          groups.flatMap(([vaultAccount, mint, userAccount]) => [
+            { pubkey: vaultAccount, isWritable: true, isSigner: false },
+            { pubkey: mint, isWritable: true, isSigner: false },
+            { pubkey: userAccount, isWritable: true, isSigner: false },
+          ])
+        )
+        .signers([owner])
+        .rpc();
+
+    before(async () => {
+      await provider.sendAndConfirm(
+        new anchor.web3.Transaction().add(
+          anchor.web3.SystemProgram.transfer({
+            fromPubkey: user.publicKey,
+            toPubkey: owner.publicKey,
+            lamports: 1_000_000_000,
+          })
+        )
+      );
+
+      await vaultProgram.methods
+        .initialize(null)
+        .accounts({
+          user: owner.publicKey,
+          vaultState: ownerStatePDA,
+          vault: ownerVaultPDA,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+
+      for (const tokenProgram of [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID]) {
+        const mint = await createMint(
+          provider.connection,
+          owner,
+          owner.publicKey,
+          null,
+          6,
+          undefined,
+          undefined,
+          tokenProgram
+        );
+        const account = await getOrCreateAssociatedTokenAccount(
+          provider.connection,
+          owner,
+          mint,
+          owner.publicKey,
+          false,
+          undefined,
+          undefined,
+          tokenProgram
+        );
+        await mintTo(
+          provider.connection,
+          owner,
+          mint,
+          account.address,
+          owner,
+          1_000_000,
+          [],
+          undefined,
+          tokenProgram
+        );
+        mints.push({ mint, tokenProgram });
+      }
+    });
+
+    it("Deposits and withdraws SPL Token and Token-2022 tokens", async () => {
+      for (let i = 0; i < mints.length; i++) {
+        // The first deposit creates the vault's token account for the mint
+        await vaultProgram.methods
+          .depositToken(new anchor.BN(600_000))
+          .accounts(tokenPayment(i))
+          .signers([owner])
+          .rpc();
+        expect(await balance(vaultAta(i), i)).to.equal(600_000);
+        expect(await balance(userAta(i), i)).to.equal(400_000);
+
+        await vaultProgram.methods
+          .withdrawToken(new anchor.BN(100_000))
+          .accounts(tokenPayment(i))
+          .signers([owner])
+          .rpc();
+        expect(await balance(vaultAta(i), i)).to.equal(500_000);
+        expect(await balance(userAta(i), i)).to.equal(500_000);
+      }
+    });
+
+    it("Rejects a token account group that mixes mints", async () => {
+      // The vault's SPL Token account paired with the Token-2022 mint and user account
+      try {
+        await closeVault([[vaultAta(0), mints[1].mint, userAta(1)]]);
+        expect.fail("A mismatched group should be rejected");
+      } catch (error) {
+        expect(error.toString()).to.include("InvalidTokenAccounts");
+      }
+      expect(await balance(vaultAta(0), 0)).to.equal(500_000);
+    });
+
+    it("Sweeps and closes every token account when closing the vault", async () => {
+      await closeVault(
+        mints.map(({ mint }, i) => [vaultAta(i), mint, userAta(i)])
+      );
+
+      for (let i = 0; i < mints.length; i++) {
+        expect(await provider.connection.getAccountInfo(vaultAta(i))).to.be.null;
+        expect(await balance(userAta(i), i)).to.equal(1_000_000);
+      }
+      expect(await provider.connection.getAccountInfo(ownerStatePDA)).to.be.null;
+    });
+  });
+
+  describe("token vault with a transfer fee", () => {
+    const owner = anchor.web3.Keypair.generate();
+    const mint = anchor.web3.Keypair.generate();
+    const [ownerVaultPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), owner.publicKey.toBuffer()],
+      vaultProgram.programId
+    );
+    const [ownerStatePDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("state"), owner.publicKey.toBuffer()],
+      vaultProgram.programId
+    );
+    const vaultAta = getAssociatedTokenAddressSync(
+      mint.publicKey,
+      ownerVaultPDA,
+      true,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const userAta = getAssociatedTokenAddressSync(
+      mint.publicKey,
+      owner.publicKey,
+      false,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    before(async () => {
+      await provider.sendAndConfirm(
+        new anchor.web3.Transaction().add(
+          anchor.web3.SystemProgram.transfer({
+            fromPubkey: user.publicKey,
+            toPubkey: owner.publicKey,
+            lamports: 1_000_000_000,
+          })
+        )
+      );
+
+      await vaultProgram.methods
+        .initialize(null)
+        .accounts({
+          user: owner.publicKey,
+          vaultState: ownerStatePDA,
+          vault: ownerVaultPDA,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+
+      // Token-2022 mint with a 1% transfer fee
+      const space = getMintLen([ExtensionType.TransferFeeConfig]);
+      await provider.sendAndConfirm(
+        new anchor.web3.Transaction().add(
+          anchor.web3.SystemProgram.createAccount({
+            fromPubkey: user.publicKey,
+            newAccountPubkey: mint.publicKey,
+            space,
+            lamports: await provider.connection.getMinimumBalanceForRentExemption(space),
+            programId: TOKEN_2022_PROGRAM_ID,
+          }),
+          createInitializeTransferFeeConfigInstruction(
+            mint.publicKey,
+            owner.publicKey,
+            owner.publicKey,
+            100,
+            BigInt(1_000_000),
+            TOKEN_2022_PROGRAM_ID
+          ),
+          createInitializeMintInstruction(
+            mint.publicKey,
+            6,
+            owner.publicKey,
+            null,
+            TOKEN_2022_PROGRAM_ID
+          )
+        ),
+        [mint]
+      );
+      const account = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        owner,
+        mint.publicKey,
+        owner.publicKey,
+        false,
+        undefined,
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+      await mintTo(
+        provider.connection,
+        owner,
+        mint.publicKey,
+        account.address,
+        owner,
+        1_000_000,
+        [],
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+    });
+
+    it("Harvests withheld fees so close_vault can close the token account", async () => {
+      // 1% of the deposit is withheld in the vault's token account
+      await vaultProgram.methods
+        .depositToken(new anchor.BN(100_000))
+        .accounts({
+          user: owner.publicKey,
+          vaultState: ownerStatePDA,
+          vault: ownerVaultPDA,
+          mint: mint.publicKey,
+          userAta,
+          vaultAta,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+      const deposited = await getAccount(provider.connection, vaultAta, undefined, TOKEN_2022_PROGRAM_ID);
+      expect(Number(deposited.amount)).to.equal(99_000);
+
+      await vaultProgram.methods
+        .closeVault()
+        .accounts({
+          user: owner.publicKey,
+          vaultState: ownerStatePDA,
+          vault: ownerVaultPDA,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          token2022Program: TOKEN_2022_PROGRAM_ID,
+        })
+        .remainingAccounts([
+          { pubkey: vaultAta, isWritable: true, isSigner: false },
+          { pubkey: mint.publicKey, isWritable: true, isSigner: false },
+          { pubkey: userAta, isWritable: true, isSigner: false },
+        ])
+        .signers([owner])
+        .rpc();
+
+      // The sweep back out pays 1% again, withheld in the user's account
+      expect(await provider.connection.getAccountInfo(vaultAta)).to.be.null;
+      const swept = await getAccount(provider.connection, userAta, undefined, TOKEN_2022_PROGRAM_ID);
+      expect(Number(swept.amount)).to.equal(900_000 + 99_000 - 990);
+      expect(await provider.connection.getAccountInfo(ownerStatePDA)).to.be.null;
+    });
+  });
 });